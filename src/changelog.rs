@@ -1,214 +1,855 @@
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 use chrono::Local;
 use colored::Colorize;
-use regex::Regex;
-
-use crate::lexer::{default_config, parse_commit_message};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::git::{self, GitCommit};
+use crate::lexer::{parse_commit_message, CommitMessage, Config};
+use crate::packages::PackageConfig;
+use crate::version::TagPattern;
+
+pub mod contributors;
+pub mod document;
+pub mod formats;
+pub mod links;
+pub mod lint;
+pub mod reverts;
+pub mod template;
+
+use contributors::{Author, ContributorsConfig};
+use document::Changelog;
+use formats::{Format, Packaging};
+use links::{Forge, Links};
 
 const CHANGELOG_FILE: &str = "CHANGELOG.md";
 
-pub fn init() -> Result<(), String> {
-    if Path::new(CHANGELOG_FILE).exists() {
-        return Err(format!("{} already exists.", CHANGELOG_FILE));
-    }
-
-    let header = r#"# Changelog
+const DEFAULT_HEADER: &str = r#"# Changelog
 
 All notable changes to this project will be documented in this file.
 
 The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
 and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
-
-## [Unreleased]
 "#;
 
-    fs::write(CHANGELOG_FILE, header).map_err(|e| e.to_string())?;
-    println!("{} Created {}", "✔".green(), CHANGELOG_FILE);
-    Ok(())
+/// The `[changelog]` table of `.convy.toml`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ChangelogConfig {
+    pub path: Option<String>,             // Changelog file, defaults to CHANGELOG.md
+    pub header: Option<String>,           // Text written above the first version by `init`
+    pub hidden_types: Option<Vec<String>>, // Types left out of the generated sections
+    pub sections: Option<Vec<SectionConfig>>,
+    pub template: Option<String>,         // Built-in template name or path to a template file
+    pub inline_template: Option<String>,  // Template text, takes precedence over `template`
+    pub forge: Option<Forge>,             // Hosting service, when it cannot be told from the remote URL
+    pub commit_url: Option<String>,       // e.g. "{remote}/commit/{hash}"
+    pub issue_url: Option<String>,        // e.g. "{remote}/issues/{issue}"
+    pub compare_url: Option<String>,      // e.g. "{remote}/compare/{previous}...{current}"
+    pub tag_url: Option<String>,          // e.g. "{remote}/releases/tag/{tag}", links the first release
+    pub style: Option<ChangelogStyle>,    // Default sections and template; `sections`/`template` still override
+    pub packaging: Option<PackagingConfig>, // Used by the debian and rpm formats
+    pub contributors: Option<ContributorsConfig>, // Lists each version's contributors when present
 }
 
-pub fn generate(write: bool, all: bool) -> Result<(), String> {
-    // 1. Find range
-    let range = if all {
-        "HEAD".to_string()
-    } else {
-        // Try to find the latest tag
-        let output = Command::new("git")
-            .args(["describe", "--tags", "--abbrev=0"])
-            .output()
-            .map_err(|e| e.to_string())?;
-
-        if output.status.success() {
-            let tag = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            format!("{}..HEAD", tag)
-        } else {
-            // No tags found, assume all
-            "HEAD".to_string()
+/// The `[changelog.packaging]` table: how `--format debian` and `--format rpm` describe the package.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PackagingConfig {
+    pub name: Option<String>,         // Package name, defaults to the selected package or the directory name
+    pub maintainer: Option<String>,   // "Name <email>", defaults to git's user.name and user.email
+    pub distribution: Option<String>, // Debian distribution of released versions, defaults to "unstable"
+    pub urgency: Option<String>,      // Debian urgency, defaults to "medium"
+    pub revision: Option<String>,     // Debian revision / RPM release appended to the version, defaults to "1"
+}
+
+/// The section layout a changelog follows when no `[[changelog.sections]]` are configured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogStyle {
+    /// conventional-changelog headings: Features, Bug Fixes, ... plus a breaking changes section.
+    #[default]
+    Conventional,
+    /// Keep a Changelog categories: Added, Changed, Deprecated, Removed, Fixed and Security.
+    KeepAChangelog,
+}
+
+/// One `[[changelog.sections]]` entry: the heading used for commits of the listed types.
+///
+/// Sections are rendered in the order they are configured. A commit carrying one of a section's `footers` goes there
+/// first, then a breaking commit goes to a `breaking` section, and otherwise the commit type decides. A `catch_all`
+/// section collects every type that no other section lists, and a `hidden` section swallows its commits without
+/// rendering them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SectionConfig {
+    pub title: String,
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub footers: Vec<String>, // e.g. ["Deprecated"] for commits with a `Deprecated:` footer
+    pub breaking: Option<bool>,
+    pub hidden: Option<bool>,
+    pub catch_all: Option<bool>,
+}
+
+impl SectionConfig {
+    fn new(title: &str, types: &[&str]) -> SectionConfig {
+        SectionConfig {
+            title: title.to_string(),
+            types: types.iter().map(|t| t.to_string()).collect(),
+            footers: Vec::new(),
+            breaking: None,
+            hidden: None,
+            catch_all: None,
         }
-    };
+    }
 
-    println!("{} Generating changelog for range: {}", "ℹ".blue(), range);
+    fn is_hidden(&self) -> bool {
+        self.hidden.unwrap_or(false)
+    }
 
-    // 2. Get commits
-    let output = Command::new("git")
-        .args(["log", &range, "--format=%B%n---CONVY_DELIM---"])
-        .output()
-        .map_err(|e| e.to_string())?;
+    fn is_catch_all(&self) -> bool {
+        self.catch_all.unwrap_or(false)
+    }
 
-    if !output.status.success() {
-        return Err("Failed to read git log".to_string());
+    fn takes_footer_of(&self, commit: &CommitMessage) -> bool {
+        commit.footers.keys().any(|key| self.footers.iter().any(|f| f.eq_ignore_ascii_case(key)))
     }
+}
 
-    let raw_log = String::from_utf8_lossy(&output.stdout);
-    let raw_commits: Vec<&str> = raw_log.split("---CONVY_DELIM---\n").collect();
+fn default_sections() -> Vec<SectionConfig> {
+    vec![
+        SectionConfig::new("Features", &["feat"]),
+        SectionConfig::new("Bug Fixes", &["fix"]),
+        SectionConfig::new("Performance Improvements", &["perf"]),
+        SectionConfig::new("Reverts", &["revert"]),
+        SectionConfig::new("Documentation", &["docs"]),
+        SectionConfig::new("Code Refactoring", &["refactor"]),
+        SectionConfig { catch_all: Some(true), ..SectionConfig::new("Other Changes", &[]) },
+    ]
+}
 
-    let mut feats = Vec::new();
-    let mut fixes = Vec::new();
-    let mut docs = Vec::new();
-    let mut other = Vec::new();
-    let mut breaking = Vec::new();
+// Keep a Changelog only lists changes users notice, so chores, docs, tests and the like are hidden
+fn keepachangelog_sections() -> Vec<SectionConfig> {
+    vec![
+        SectionConfig::new("Added", &["feat"]),
+        SectionConfig { breaking: Some(true), ..SectionConfig::new("Changed", &["perf", "refactor", "revert"]) },
+        SectionConfig { footers: vec!["Deprecated".to_string()], ..SectionConfig::new("Deprecated", &[]) },
+        SectionConfig { footers: vec!["Removed".to_string()], ..SectionConfig::new("Removed", &[]) },
+        SectionConfig::new("Fixed", &["fix"]),
+        SectionConfig { footers: vec!["Security".to_string()], ..SectionConfig::new("Security", &[]) },
+        SectionConfig { catch_all: Some(true), hidden: Some(true), ..SectionConfig::new("Other", &[]) },
+    ]
+}
 
-    let config = default_config();
+/// A parsed commit together with the git metadata the changelog needs.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Paths the commit touched, relative to the repository root.
+    pub files: Vec<String>,
+    /// People credited in `Co-authored-by` footers.
+    pub co_authors: Vec<Author>,
+    pub commit: CommitMessage,
+}
 
-    for raw_msg in raw_commits {
-        if raw_msg.trim().is_empty() {
-            continue;
+impl Entry {
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    /// The author followed by the co-authors.
+    pub fn authors(&self) -> Vec<Author> {
+        let author = Author { name: self.author_name.clone(), email: self.author_email.clone() };
+        std::iter::once(author).chain(self.co_authors.iter().cloned()).collect()
+    }
+
+    /// An entry for `message` as Jane Doe committed it, for tests; override fields with struct update syntax.
+    #[cfg(test)]
+    pub(crate) fn for_test(message: &str) -> Entry {
+        let commit = parse_commit_message(message, crate::lexer::default_config()).unwrap();
+        Entry {
+            hash: "0123456789abcdef".to_string(),
+            author_name: "Jane Doe".to_string(),
+            author_email: "jane@example.com".to_string(),
+            files: Vec::new(),
+            co_authors: contributors::co_authors(&commit),
+            commit,
         }
+    }
+}
 
-        if let Ok(commit) = parse_commit_message(raw_msg.trim(), config.borrow_config()) {
-            let desc = format!("{}: {}", 
-                commit.scope.as_ref().map(|s| format!("**{}**", s)).unwrap_or_default(),
-                commit.subject
-            ).trim_start_matches(": ").to_string();
+/// Commits grouped under one changelog heading.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: String,
+    pub commits: Vec<Entry>,
+}
 
-            // Check for breaking changes
-            let is_breaking = raw_msg.contains("BREAKING CHANGE") || raw_msg.contains("!");
-            if is_breaking {
-                breaking.push(desc.clone());
-            }
+/// The grouped changes for one version (or the Unreleased block).
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseNotes {
+    /// `None` for unreleased changes.
+    pub version: Option<String>,
+    pub date: Option<String>,
+    /// The tag the version is (or will be) released under.
+    pub tag: Option<String>,
+    pub previous_tag: Option<String>,
+    pub breaking: Vec<Entry>,
+    pub sections: Vec<Section>,
+    /// Everyone behind the version's commits, when `[changelog.contributors]` is configured.
+    pub contributors: Vec<Author>,
+    /// The contributors whose first commit is in this version.
+    pub new_contributors: Vec<Author>,
+}
 
-            match commit.commit_type.as_str() {
-                "feat" => feats.push(desc),
-                "fix" => fixes.push(desc),
-                "docs" => docs.push(desc),
-                _ => other.push(format!("{}: {}", commit.commit_type, desc)),
-            }
+impl ReleaseNotes {
+    pub fn is_empty(&self) -> bool {
+        self.breaking.is_empty() && self.sections.iter().all(|s| s.commits.is_empty())
+    }
+}
+
+impl ChangelogConfig {
+    pub fn path(&self) -> &str {
+        self.path.as_deref().unwrap_or(CHANGELOG_FILE)
+    }
+
+    pub fn header(&self) -> &str {
+        self.header.as_deref().unwrap_or(DEFAULT_HEADER)
+    }
+
+    pub fn style(&self) -> ChangelogStyle {
+        self.style.unwrap_or_default()
+    }
+
+    pub fn sections(&self) -> Vec<SectionConfig> {
+        self.sections.clone().unwrap_or_else(|| match self.style() {
+            ChangelogStyle::Conventional => default_sections(),
+            ChangelogStyle::KeepAChangelog => keepachangelog_sections(),
+        })
+    }
+
+    pub fn is_hidden(&self, commit_type: &str) -> bool {
+        self.hidden_types.iter().flatten().any(|t| t == commit_type)
+    }
+
+    /// The template text used to render each version's notes.
+    pub fn template_source(&self) -> Result<String, String> {
+        if let Some(inline) = &self.inline_template {
+            return Ok(inline.clone());
+        }
+        let name = self.template.as_deref().unwrap_or(match self.style() {
+            ChangelogStyle::Conventional => "default",
+            ChangelogStyle::KeepAChangelog => "keepachangelog",
+        });
+        match template::builtin(name) {
+            Some(builtin) => Ok(builtin.to_string()),
+            None => fs::read_to_string(name).map_err(|e| format!("Failed to read changelog template {}: {}", name, e)),
         }
     }
+}
 
-    // 3. Render Markdown
-    let mut md = String::new();
-    
-    if !breaking.is_empty() {
-        md.push_str("### ⚠ BREAKING CHANGES\n");
-        for c in breaking { md.push_str(&format!("- {}\n", c)); }
-        md.push('\n');
+impl Config {
+    /// The `[changelog]` settings, falling back to defaults when the table is absent.
+    pub fn changelog(&self) -> ChangelogConfig {
+        self.changelog.clone().unwrap_or_default()
     }
-    
-    if !feats.is_empty() {
-        md.push_str("### Features\n");
-        for c in feats { md.push_str(&format!("- {}\n", c)); }
-        md.push('\n');
+}
+
+pub fn init(config: &Config) -> Result<(), String> {
+    let changelog = config.changelog();
+    let path = changelog.path();
+    if Path::new(path).exists() {
+        return Err(format!("{} already exists.", path));
     }
 
-    if !fixes.is_empty() {
-        md.push_str("### Bug Fixes\n");
-        for c in fixes { md.push_str(&format!("- {}\n", c)); }
-        md.push('\n');
+    create(&changelog)?;
+    println!("{} Created {}", "✔".green(), path);
+    Ok(())
+}
+
+// Writes a changelog holding just the header and an empty [Unreleased] section
+fn create(changelog: &ChangelogConfig) -> Result<(), String> {
+    let header = format!("{}\n\n## [Unreleased]\n", changelog.header().trim_end());
+    fs::write(changelog.path(), header).map_err(|e| e.to_string())
+}
+
+/// Renders the changes since the last version tag (or the whole history with `full`) in `format`. With `write` the
+/// Markdown is spliced into the changelog file instead of printed.
+pub fn generate(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    write: bool,
+    all: bool,
+    full: bool,
+    format: Format,
+) -> Result<(), String> {
+    if write && format != Format::Markdown {
+        return Err("--write only supports the markdown format".to_string());
     }
-    
-    if !docs.is_empty() {
-        md.push_str("### Documentation\n");
-        for c in docs { md.push_str(&format!("- {}\n", c)); }
-        md.push('\n');
+    if full {
+        return generate_full(config, package, write, format);
+    }
+
+    let changelog = config.changelog();
+    let changelog_file = changelog.path();
+
+    // 1. Find range
+    let previous_tag = if all { None } else { latest_version_tag(&config.tag_pattern()?)? };
+    let range = match &previous_tag {
+        Some(tag) => format!("{}..HEAD", tag),
+        // No tags found (or --all), include everything
+        None => "HEAD".to_string(),
+    };
+
+    info(format, format!("Generating changelog for range: {}", range));
+
+    // 2. Get commits, then group and render Markdown
+    let mut notes = collect_notes(&range, config, package, &changelog)?;
+    notes.previous_tag = previous_tag;
+    let links = Links::resolve(&changelog);
+    if format != Format::Markdown {
+        println!("{}", render_standalone(config, package, format, vec![notes], &links)?);
+        return Ok(());
     }
+    let md = template::render(&changelog.template_source()?, &notes, &links)?;
 
     // 4. Output
     if write {
-        if !Path::new(CHANGELOG_FILE).exists() {
-            return Err(format!("{} not found. Run `init` first.", CHANGELOG_FILE));
+        if !Path::new(changelog_file).exists() {
+            return Err(format!("{} not found. Run `init` first.", changelog_file));
         }
+        write_unreleased(&changelog, &md, notes.previous_tag.as_deref(), &links)?;
+        println!("{} Updated {}", "✔".green(), changelog_file);
+    } else {
+        println!("{}", md);
+    }
 
-        let content = fs::read_to_string(CHANGELOG_FILE).map_err(|e| e.to_string())?;
-        
-        // Regex to find [Unreleased] section
-        // We replace everything between "## [Unreleased]" and the next "## [" or EOF
-        let re = Regex::new(r"(?s)(## \[Unreleased\]\n)(.*?)(## \[|$)").unwrap();
-        
-        let new_content = if re.is_match(&content) {
-            re.replace(&content, |caps: &regex::Captures| {
-                format!("{}{}{}", &caps[1], md, &caps[3])
-            }).to_string()
-        } else {
-            // If no [Unreleased] section found, try to insert after header
-             // This is a simple fallback
-            if content.contains("## [Unreleased]") {
-                 // Should have been caught by regex, maybe formatting weirdness
-                 content + "\n" + &md
-            } else {
-                // Insert at top? No, usually after header.
-                 // Try to find the end of the header
-                 let split_idx = content.find("## [").unwrap_or(content.len());
-                 let (head, tail) = content.split_at(split_idx);
-                 format!("{}## [Unreleased]\n\n{}{}", head, md, tail)
-            }
+    Ok(())
+}
+
+// Splices freshly rendered Unreleased notes into the changelog file and points its link reference at the changes
+// since `previous_tag`
+fn write_unreleased(changelog: &ChangelogConfig, md: &str, previous_tag: Option<&str>, links: &Links) -> Result<(), String> {
+    let path = changelog.path();
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut document = Changelog::parse(&content);
+    document.set_unreleased_generated(md);
+    if let Some(url) = previous_tag.and_then(|tag| links.compare(Some(tag), "HEAD")) {
+        document.set_reference("Unreleased", &url);
+    }
+    fs::write(path, document.to_string()).map_err(|e| e.to_string())
+}
+
+/// Rebuilds the whole changelog, one dated section per version tag plus the Unreleased changes.
+fn generate_full(config: &Config, package: Option<&PackageConfig>, write: bool, format: Format) -> Result<(), String> {
+    let changelog = config.changelog();
+    let links = Links::resolve(&changelog);
+    let template = changelog.template_source()?;
+    let tags = version_tags(&config.tag_pattern()?)?;
+
+    info(format, format!("Generating changelog for {} version tag(s)", tags.len()));
+
+    // Oldest first, so each version knows the tag before it
+    let mut releases = Vec::new();
+    let mut previous: Option<&git::Tag> = None;
+    for (version, tag) in &tags {
+        let range = match previous {
+            Some(prev) => format!("{}..{}", prev.name, tag.name),
+            None => tag.name.clone(),
         };
+        let mut notes = collect_notes(&range, config, package, &changelog)?;
+        notes.version = Some(version.to_string());
+        notes.date = Some(tag.date.clone());
+        notes.tag = Some(tag.name.clone());
+        notes.previous_tag = previous.map(|t| t.name.clone());
+        releases.push((notes, tag));
+        previous = Some(tag);
+    }
+
+    let last_tag = previous.map(|t| t.name.clone());
+    let range = match &last_tag {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    let mut unreleased = collect_notes(&range, config, package, &changelog)?;
+    unreleased.previous_tag = last_tag.clone();
+
+    if format != Format::Markdown {
+        let newest_first: Vec<ReleaseNotes> =
+            std::iter::once(unreleased).chain(releases.into_iter().rev().map(|(notes, _)| notes)).collect();
+        println!("{}", render_standalone(config, package, format, newest_first, &links)?);
+        return Ok(());
+    }
+
+    let mut content = format!("{}\n\n## [Unreleased]\n\n", changelog.header().trim_end());
+    for (notes, _) in releases.iter().rev() {
+        if !content.ends_with("\n\n") {
+            content.push('\n');
+        }
+        content.push_str(&format!(
+            "## [{}] - {}\n",
+            notes.version.as_deref().unwrap_or_default(),
+            notes.date.as_deref().unwrap_or_default()
+        ));
+        content.push_str(&template::render(&template, notes, &links)?);
+    }
 
-        fs::write(CHANGELOG_FILE, new_content).map_err(|e| e.to_string())?;
-        println!("{} Updated {}", "✔".green(), CHANGELOG_FILE);
+    // Unreleased first, then every version newest first (each one is inserted below Unreleased)
+    let mut document = Changelog::parse(&content);
+    document.set_unreleased_generated(&template::render(&template, &unreleased, &links)?);
+    if let Some(url) = last_tag.as_deref().and_then(|tag| links.compare(Some(tag), "HEAD")) {
+        document.set_reference("Unreleased", &url);
+    }
+    for (notes, tag) in &releases {
+        if let Some(url) = links.compare(notes.previous_tag.as_deref(), &tag.name) {
+            document.set_reference(notes.version.as_deref().unwrap_or_default(), &url);
+        }
+    }
+    let content = document.to_string();
 
+    if write {
+        let changelog_file = changelog.path();
+        fs::write(changelog_file, content).map_err(|e| e.to_string())?;
+        println!("{} Rewrote {}", "✔".green(), changelog_file);
     } else {
-        println!("{}", md);
+        println!("{}", content);
+    }
+    Ok(())
+}
+
+// Renders a format other than the changelog file's Markdown. Distribution changelogs need a version for every
+// entry, so pending changes are shown under the version `convy version next` would pick.
+fn render_standalone(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    format: Format,
+    mut releases: Vec<ReleaseNotes>,
+    links: &Links,
+) -> Result<String, String> {
+    let changelog = config.changelog();
+    let packaging = match format {
+        Format::Debian | Format::Rpm => {
+            // An empty Unreleased block would become a stanza for a version with no changes
+            if releases.len() > 1 {
+                releases.retain(|n| n.version.is_some() || !n.is_empty());
+            }
+            for notes in releases.iter_mut().filter(|n| n.version.is_none()) {
+                notes.version = Some(crate::version::next_version(config, package, None)?.next.to_string());
+            }
+            packaging(package, &changelog)?
+        }
+        _ => Packaging::default(),
+    };
+    formats::render(format, &releases, links, &changelog.template_source()?, &packaging)
+}
+
+// `[changelog.packaging]` with its defaults filled in from the selected package, the directory and git
+fn packaging(package: Option<&PackageConfig>, changelog: &ChangelogConfig) -> Result<Packaging, String> {
+    let packaging = changelog.packaging.clone().unwrap_or_default();
+    let name = match (packaging.name, package) {
+        (Some(name), _) => name,
+        (None, Some(package)) => package.name.clone(),
+        (None, None) => std::env::current_dir()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().to_lowercase()))
+            .ok_or("Set [changelog.packaging] name")?,
+    };
+    let maintainer = match packaging.maintainer {
+        Some(maintainer) => maintainer,
+        None => match (git::config_value("user.name"), git::config_value("user.email")) {
+            (Some(user), Some(email)) => format!("{} <{}>", user, email),
+            _ => return Err("Set [changelog.packaging] maintainer or git's user.name and user.email".to_string()),
+        },
+    };
+    Ok(Packaging {
+        name,
+        maintainer,
+        distribution: packaging.distribution.unwrap_or_else(|| "unstable".to_string()),
+        urgency: packaging.urgency.unwrap_or_else(|| "medium".to_string()),
+        revision: packaging.revision.unwrap_or_else(|| "1".to_string()),
+    })
+}
+
+// Progress messages go to stderr when stdout carries a machine-readable or standalone document
+fn info(format: Format, message: String) {
+    match format {
+        Format::Markdown => println!("{} {}", "ℹ".blue(), message),
+        _ => eprintln!("{} {}", "ℹ".blue(), message),
+    }
+}
+
+/// Tags reachable from HEAD that match the tag pattern, sorted from oldest to newest version. Pre-releases sort
+/// before their final release, and `rc.10` after `rc.9`; tags such as `deploy-prod` are ignored.
+pub fn version_tags(pattern: &TagPattern) -> Result<Vec<(Version, git::Tag)>, String> {
+    let mut tags: Vec<(Version, git::Tag)> = git::tags()?
+        .into_iter()
+        .filter_map(|tag| Some((pattern.parse(&tag.name)?, tag)))
+        .collect();
+    tags.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(tags)
+}
+
+// The newest version tag, including pre-releases
+fn latest_version_tag(pattern: &TagPattern) -> Result<Option<String>, String> {
+    Ok(version_tags(pattern)?.pop().map(|(_, tag)| tag.name))
+}
+
+/// Parses a version given on the command line, accepting a leading `v`.
+pub fn parse_version(version: &str) -> Result<Version, String> {
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).map_err(|e| {
+        format!("Invalid version '{}': {} (expected e.g. 1.4.0, 1.4.0-rc.1 or 1.4.0+build.5)", version, e)
+    })
+}
+
+// Reads, groups and reports the commits in `range`, ready for rendering. The report goes to stderr so it never ends
+// up inside a machine-readable document.
+fn collect_notes(
+    range: &str,
+    config: &Config,
+    package: Option<&PackageConfig>,
+    changelog: &ChangelogConfig,
+) -> Result<ReleaseNotes, String> {
+    let commits = read_commits(range, config, package)?;
+    // Every commit counts towards the contributors, including those of hidden types
+    let (contributors, new_contributors) = match &changelog.contributors {
+        Some(settings) => {
+            let contributors = contributors::contributors(&commits, settings);
+            let new_contributors = match settings.new_contributors() {
+                true => contributors::new_contributors(&contributors, range.split_once("..").map(|(from, _)| from))?,
+                false => Vec::new(),
+            };
+            (contributors, new_contributors)
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+    let (mut notes, unmatched) = group_commits(commits, changelog);
+    notes.contributors = contributors;
+    notes.new_contributors = new_contributors;
+    if !unmatched.is_empty() {
+        let mut types: Vec<&str> = unmatched.iter().map(|e| e.commit.commit_type.as_str()).collect();
+        types.sort_unstable();
+        types.dedup();
+        eprintln!(
+            "{} {} commit(s) in {} matched no changelog section ({}). Add a section with `catch_all = true` to include them.",
+            "!".yellow(),
+            unmatched.len(),
+            range,
+            types.join(", ")
+        );
+    }
+    Ok(notes)
+}
+
+// Reads and parses every commit in a git revision range, newest first; unparseable commits are skipped, and commits
+// reverted within the range are dropped along with their reverts. With a package, only the commits it owns are kept.
+pub fn read_commits(range: &str, config: &Config, package: Option<&PackageConfig>) -> Result<Vec<Entry>, String> {
+    let mut entries: Vec<Entry> = git::log(range)?.into_iter().filter_map(|c| to_entry(c, config)).collect();
+    if entries.iter().any(|e| release_commit_tags(e).is_some()) {
+        let tags: Vec<String> = git::tags()?.into_iter().map(|t| t.name).collect();
+        entries.retain(|e| !release_commit_tags(e).is_some_and(|listed| listed.iter().all(|t| tags.iter().any(|tag| tag == t))));
+    }
+    let mut entries: Vec<Entry> = reverts::cancel(entries)
+        .into_iter()
+        .filter(|entry| package.is_none_or(|package| package.owns(entry)))
+        .collect();
+    contributors::apply_mailmap(&mut entries)?;
+    Ok(entries)
+}
+
+// The tags named by a commit `convy release` made (`chore(release): v1.2.0` or `chore(release): api@1.0.0, web@2.1.0`).
+// Such a commit only records the release, so it is left out once its tags exist.
+fn release_commit_tags(entry: &Entry) -> Option<Vec<&str>> {
+    let commit = &entry.commit;
+    let generated = commit.commit_type == "chore"
+        && commit.scope.as_deref() == Some("release")
+        && !commit.breaking
+        && commit.body.is_none()
+        && commit.footers.is_empty();
+    generated.then(|| commit.subject.split(", ").collect())
+}
+
+fn to_entry(git_commit: GitCommit, config: &Config) -> Option<Entry> {
+    // `git revert` writes `Revert "feat: x"`, which is read as `revert: feat: x`
+    let commit = parse_commit_message(&git_commit.message, config.clone())
+        .ok()
+        .or_else(|| parse_commit_message(&reverts::conventional_message(&git_commit.message)?, config.clone()).ok())?;
+    Some(Entry {
+        hash: git_commit.hash,
+        author_name: git_commit.author_name,
+        author_email: git_commit.author_email,
+        files: git_commit.files,
+        co_authors: contributors::co_authors(&commit),
+        commit,
+    })
+}
+
+/// Sorts commits into the configured sections.
+///
+/// Returns the grouped notes and the commits that matched no section at all (neither by type nor by a catch-all),
+/// so callers can report them instead of dropping them silently.
+pub fn group_commits(entries: Vec<Entry>, changelog: &ChangelogConfig) -> (ReleaseNotes, Vec<Entry>) {
+    let configured = changelog.sections();
+    let mut notes = ReleaseNotes {
+        sections: configured
+            .iter()
+            .map(|s| Section { title: s.title.clone(), commits: Vec::new() })
+            .collect(),
+        ..ReleaseNotes::default()
+    };
+    let mut unmatched = Vec::new();
+
+    for entry in entries {
+        let commit = &entry.commit;
+        let breaking = commit.breaking || commit.footers.contains_key("BREAKING-CHANGE");
+        // Breaking changes are always listed, even for hidden types
+        if breaking {
+            notes.breaking.push(entry.clone());
+        }
+
+        // Footers and breaking changes pick their section before the type is looked at, so they are never hidden
+        let special = configured
+            .iter()
+            .position(|s| s.takes_footer_of(commit))
+            .or_else(|| configured.iter().position(|s| breaking && s.breaking.unwrap_or(false)));
+        if special.is_none() && changelog.is_hidden(&commit.commit_type) {
+            continue;
+        }
+
+        let index = special
+            .or_else(|| configured.iter().position(|s| s.types.contains(&commit.commit_type)))
+            .or_else(|| configured.iter().position(SectionConfig::is_catch_all));
+        match index {
+            Some(i) if configured[i].is_hidden() => {}
+            Some(i) => notes.sections[i].commits.push(entry),
+            None => unmatched.push(entry),
+        }
+    }
+
+    notes.sections.retain(|s| !s.commits.is_empty());
+    (notes, unmatched)
+}
+
+/// Promotes the Unreleased changes to `version`, or to the next computed version when none is given.
+///
+/// Releasing a final version folds the sections of its pre-releases (`1.4.0-rc.1`, ...) into it.
+pub fn release(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    version: Option<&str>,
+    prerelease: Option<&str>,
+) -> Result<(), String> {
+    let changelog = config.changelog();
+    let changelog_file = changelog.path();
+    if !Path::new(changelog_file).exists() {
+        return Err(format!("{} not found. Run `init` first.", changelog_file));
     }
 
+    let version = match version {
+        Some(version) => parse_version(version)?,
+        None => {
+            let next = crate::version::next_version(config, package, prerelease)?;
+            if next.bump == crate::version::Bump::None {
+                return Err("No commits since the last release call for a new version.".to_string());
+            }
+            next.next
+        }
+    };
+    let collapsed = promote(config, package, &version)?;
+    if !collapsed.is_empty() {
+        println!("{} Folded {} into {}", "ℹ".blue(), collapsed.join(", "), version);
+    }
+    println!("{} Released version {} in {}", "✔".green(), version, changelog_file);
     Ok(())
 }
 
-pub fn release(version: &str) -> Result<(), String> {
-    if !Path::new(CHANGELOG_FILE).exists() {
-        return Err(format!("{} not found. Run `init` first.", CHANGELOG_FILE));
+/// Writes the changes since the previous release into the changelog, creating the file when it is missing, and
+/// releases them as `version`. Returns the pre-release sections folded into it. Prints nothing, so callers with
+/// their own output format can report progress themselves.
+pub fn write_release(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    version: &Version,
+) -> Result<Vec<String>, String> {
+    let changelog = config.changelog();
+    if !Path::new(changelog.path()).exists() {
+        create(&changelog)?;
+    }
+    let previous_tag = latest_version_tag(&config.tag_pattern()?)?;
+    let range = match &previous_tag {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    let links = Links::resolve(&changelog);
+    let mut notes = collect_notes(&range, config, package, &changelog)?;
+    notes.previous_tag = previous_tag;
+    let md = template::render(&changelog.template_source()?, &notes, &links)?;
+    write_unreleased(&changelog, &md, notes.previous_tag.as_deref(), &links)?;
+    promote(config, package, version)
+}
+
+// Turns [Unreleased] into a dated section for `version`, returning the labels of the folded pre-release sections
+fn promote(config: &Config, package: Option<&PackageConfig>, version: &Version) -> Result<Vec<String>, String> {
+    let changelog = config.changelog();
+    let changelog_file = changelog.path();
+    let pattern = config.tag_pattern()?;
+    let tags = version_tags(&pattern)?;
+    if let Some((latest, _)) = tags.last() {
+        if version <= latest {
+            return Err(format!("Version {} is not newer than the latest release {}.", version, latest));
+        }
     }
+    let label = version.to_string();
+
+    let content = fs::read_to_string(changelog_file).map_err(|e| e.to_string())?;
+    let mut document = Changelog::parse(&content);
 
-    let content = fs::read_to_string(CHANGELOG_FILE).map_err(|e| e.to_string())?;
-    
     // Check if version already exists
-    if content.contains(&format!("## [{}]", version)) {
-        return Err(format!("Version {} already exists in changelog.", version));
+    if document.release(&label).is_some() {
+        return Err(format!("Version {} already exists in changelog.", label));
+    }
+
+    let previous_tag = previous_release_tag(&tags, version);
+    let collapsed = remove_prerelease_sections(&mut document, version);
+    if !collapsed.is_empty() {
+        document.set_unreleased_generated(&release_notes(config, package, version)?);
     }
 
-    // Replace [Unreleased] with [Version] - Date
-    // And add a new empty [Unreleased] section on top
-    
+    // Turn [Unreleased] into [Version] - Date below a new empty [Unreleased]
     let date = Local::now().format("%Y-%m-%d").to_string();
-    let new_header = format!("## [Unreleased]\n\n## [{}] - {}", version, date);
-    
-    let new_content = content.replace("## [Unreleased]", &new_header);
-    
-    if new_content == content {
-         return Err("Could not find '## [Unreleased]' section to release.".to_string());
+    document.promote_unreleased(&label, &date)?;
+
+    // Link the new heading to its diff, and move Unreleased on to compare against the new tag
+    let links = Links::resolve(&changelog);
+    let tag = pattern.format(version);
+    if let Some(url) = links.compare(Some(&tag), "HEAD") {
+        document.set_reference("Unreleased", &url);
+    }
+    if let Some(url) = links.compare(previous_tag.as_deref(), &tag) {
+        document.set_reference(&label, &url);
     }
 
-    fs::write(CHANGELOG_FILE, new_content).map_err(|e| e.to_string())?;
-    println!("{} Released version {} in {}", "✔".green(), version, CHANGELOG_FILE);
-    
-    Ok(())
+    fs::write(changelog_file, document.to_string()).map_err(|e| e.to_string())?;
+    Ok(collapsed)
 }
 
-// Helper trait to allow passing config by value or reference if needed, 
-// but since lexer takes by value, we need to clone.
-trait BorrowConfig {
-    fn borrow_config(&self) -> crate::lexer::Config;
+/// Renders the notes for releasing `version` now: every commit since the previous release.
+pub fn release_notes(config: &Config, package: Option<&PackageConfig>, version: &Version) -> Result<String, String> {
+    let changelog = config.changelog();
+    let pattern = config.tag_pattern()?;
+    let previous_tag = previous_release_tag(&version_tags(&pattern)?, version);
+    let range = match &previous_tag {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+    let mut notes = collect_notes(&range, config, package, &changelog)?;
+    notes.version = Some(version.to_string());
+    notes.date = Some(Local::now().format("%Y-%m-%d").to_string());
+    notes.tag = Some(pattern.format(version));
+    notes.previous_tag = previous_tag;
+    template::render(&changelog.template_source()?, &notes, &Links::resolve(&changelog))
 }
 
-impl BorrowConfig for crate::lexer::Config {
-    fn borrow_config(&self) -> crate::lexer::Config {
-        crate::lexer::Config {
-             additional_types: self.additional_types.clone(),
-             scopes: self.scopes.clone(),
-             require_breaking_change_footer: self.require_breaking_change_footer,
-             emoji: self.emoji,
-        }
+// A final release compares against the previous final release, so its pre-releases are folded in
+fn previous_release_tag(tags: &[(Version, git::Tag)], version: &Version) -> Option<String> {
+    tags.iter()
+        .rev()
+        .find(|(v, _)| v < version && (!version.pre.is_empty() || v.pre.is_empty()))
+        .map(|(_, tag)| tag.name.clone())
+}
+
+// Drops the sections and link references of pre-releases of a final `version`, returning their labels
+fn remove_prerelease_sections(document: &mut Changelog, version: &Version) -> Vec<String> {
+    if !version.pre.is_empty() {
+        return Vec::new();
+    }
+    document.remove_releases(|label| {
+        Version::parse(label).is_ok_and(|v| {
+            !v.pre.is_empty() && (v.major, v.minor, v.patch) == (version.major, version.minor, version.patch)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits(messages: &[&str]) -> Vec<Entry> {
+        messages.iter().map(|m| Entry::for_test(m)).collect()
+    }
+
+    #[test]
+    fn test_default_sections_keep_every_type() {
+        let (notes, unmatched) = group_commits(
+            commits(&["feat: a", "perf: b", "refactor: c", "chore: d", "revert: e"]),
+            &ChangelogConfig::default(),
+        );
+        let titles: Vec<&str> = notes.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Features", "Performance Improvements", "Reverts", "Code Refactoring", "Other Changes"]
+        );
+        assert!(unmatched.is_empty());
+    }
+
+    #[test]
+    fn test_keepachangelog_style() {
+        let changelog = ChangelogConfig { style: Some(ChangelogStyle::KeepAChangelog), ..ChangelogConfig::default() };
+        let (notes, unmatched) = group_commits(
+            commits(&[
+                "feat: a",
+                "fix: b",
+                "chore!: c\n\nBREAKING CHANGE: drops node 16",
+                "feat: d\n\nDeprecated: use e instead",
+                "fix: f\n\nSecurity: CVE-2024-1",
+                "docs: g",
+            ]),
+            &changelog,
+        );
+        let sections: Vec<(&str, Vec<&str>)> = notes
+            .sections
+            .iter()
+            .map(|s| (s.title.as_str(), s.commits.iter().map(|c| c.commit.subject.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            sections,
+            [("Added", vec!["a"]), ("Changed", vec!["c"]), ("Deprecated", vec!["d"]), ("Fixed", vec!["b"]), ("Security", vec!["f"])]
+        );
+        assert!(unmatched.is_empty());
+        assert_eq!(changelog.template_source().unwrap(), template::KEEPACHANGELOG_TEMPLATE);
+    }
+
+    #[test]
+    fn test_remove_prerelease_sections() {
+        let content = "## [Unreleased]\n\n## [1.4.0-rc.2] - 2024-05-02\n### Bug Fixes\n- b\n\n## [1.4.0-rc.1] - 2024-05-01\n### Features\n- a\n\n## [1.3.0] - 2024-04-01\n- old\n\n[Unreleased]: u\n[1.4.0-rc.2]: r2\n[1.3.0]: f\n";
+        let mut document = Changelog::parse(content);
+        let removed = remove_prerelease_sections(&mut document, &Version::new(1, 4, 0));
+        assert_eq!(removed, vec!["1.4.0-rc.2", "1.4.0-rc.1"]);
+        assert_eq!(
+            document.to_string(),
+            "## [Unreleased]\n\n## [1.3.0] - 2024-04-01\n- old\n\n[Unreleased]: u\n[1.3.0]: f\n"
+        );
+
+        let mut document = Changelog::parse(content);
+        let removed = remove_prerelease_sections(&mut document, &Version::parse("1.4.0-rc.3").unwrap());
+        assert!(removed.is_empty());
+        assert_eq!(document.to_string(), content);
+    }
+
+    #[test]
+    fn test_hidden_and_unmatched_sections() {
+        let changelog = ChangelogConfig {
+            sections: Some(vec![
+                SectionConfig::new("Fixes", &["fix", "perf"]),
+                SectionConfig { hidden: Some(true), ..SectionConfig::new("Chores", &["chore"]) },
+            ]),
+            ..ChangelogConfig::default()
+        };
+        let (notes, unmatched) = group_commits(commits(&["perf: a", "fix: b", "chore: c", "docs: d"]), &changelog);
+        assert_eq!(notes.sections.len(), 1);
+        assert_eq!(notes.sections[0].commits.len(), 2);
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].commit.commit_type, "docs");
     }
 }
@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::Entry;
+use crate::git;
+use crate::lexer::CommitMessage;
+
+// GitHub apps commit as `name[bot]`, with an email like `123+name[bot]@users.noreply.github.com`
+const DEFAULT_EXCLUDE: &[&str] = &["*[bot]*"];
+
+/// The `[changelog.contributors]` table. Its presence adds a Contributors list to every version.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ContributorsConfig {
+    pub new_contributors: Option<bool>, // Also list people whose first commit is in the version, defaults to false
+    pub exclude: Option<Vec<String>>,   // Name or email patterns with `*` and `?`, defaults to ["*[bot]*"]
+}
+
+/// A commit author or co-author.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Author {
+    pub name: String,
+    pub email: String, // Empty when a co-author was given without one
+}
+
+impl Author {
+    /// Parses `Name <email>`; a bare name gets an empty email.
+    pub fn parse(contact: &str) -> Option<Author> {
+        let re = Regex::new(r"^(.*?)\s*<([^<>]*)>$").unwrap();
+        let contact = contact.trim();
+        let (name, email) = match re.captures(contact) {
+            Some(caps) => (caps[1].to_string(), caps[2].trim().to_string()),
+            None => (contact.to_string(), String::new()),
+        };
+        (!name.is_empty() || !email.is_empty()).then_some(Author { name, email })
+    }
+
+    fn contact(&self) -> String {
+        format!("{} <{}>", self.name, self.email)
+    }
+
+    // People are told apart by email, which varies less than the spelling of a name
+    fn key(&self) -> String {
+        match self.email.is_empty() {
+            true => self.name.to_lowercase(),
+            false => self.email.to_lowercase(),
+        }
+    }
+}
+
+impl ContributorsConfig {
+    pub fn new_contributors(&self) -> bool {
+        self.new_contributors.unwrap_or(false)
+    }
+
+    /// Whether the author's name or email matches an `exclude` pattern, ignoring case.
+    pub fn is_bot(&self, author: &Author) -> bool {
+        let patterns: Vec<&str> = match &self.exclude {
+            Some(exclude) => exclude.iter().map(String::as_str).collect(),
+            None => DEFAULT_EXCLUDE.to_vec(),
+        };
+        patterns.iter().any(|pattern| {
+            let regex = regex::escape(pattern).replace(r"\*", ".*").replace(r"\?", ".");
+            let re = Regex::new(&format!("(?i)^{}$", regex)).unwrap();
+            re.is_match(&author.name) || re.is_match(&author.email)
+        })
+    }
+}
+
+/// The people named in `Co-authored-by` footers.
+pub fn co_authors(commit: &CommitMessage) -> Vec<Author> {
+    commit
+        .footers
+        .iter()
+        .filter(|f| f.token.eq_ignore_ascii_case("Co-authored-by"))
+        .filter_map(|f| Author::parse(&f.value))
+        .collect()
+}
+
+/// Maps the co-authors of `entries` through .mailmap. Authors are read from git already mapped.
+pub fn apply_mailmap(entries: &mut [Entry]) -> Result<(), String> {
+    let contacts: Vec<String> = entries
+        .iter()
+        .flat_map(|e| &e.co_authors)
+        .filter(|a| !a.email.is_empty())
+        .map(Author::contact)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let mapped = git::check_mailmap(&contacts)?;
+    for author in entries.iter_mut().flat_map(|e| e.co_authors.iter_mut()) {
+        let Some(i) = contacts.iter().position(|c| *c == author.contact()) else {
+            continue;
+        };
+        if let Some(canonical) = mapped.get(i).and_then(|m| Author::parse(m)) {
+            *author = canonical;
+        }
+    }
+    Ok(())
+}
+
+/// Everyone who authored or co-authored one of `entries`, once each and sorted by name, leaving out bots.
+pub fn contributors(entries: &[Entry], config: &ContributorsConfig) -> Vec<Author> {
+    let mut seen = BTreeSet::new();
+    let mut authors: Vec<Author> = entries
+        .iter()
+        .flat_map(Entry::authors)
+        .filter(|a| !config.is_bot(a) && seen.insert(a.key()))
+        .collect();
+    authors.sort_by_key(|a| a.name.to_lowercase());
+    authors
+}
+
+/// The `contributors` without a commit in the history reachable from `before`; everyone when there is no history
+/// before the range.
+pub fn new_contributors(contributors: &[Author], before: Option<&str>) -> Result<Vec<Author>, String> {
+    let Some(before) = before else {
+        return Ok(contributors.to_vec());
+    };
+    let identities = git::identities(before)?;
+    let known: BTreeSet<String> = git::check_mailmap(&identities)?
+        .iter()
+        .chain(&identities)
+        .filter_map(|contact| Author::parse(contact))
+        .map(|a| a.key())
+        .collect();
+    Ok(contributors.iter().filter(|a| !known.contains(&a.key())).cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(author: &str, message: &str) -> Entry {
+        let author = Author::parse(author).unwrap();
+        Entry { author_name: author.name, author_email: author.email, ..Entry::for_test(message) }
+    }
+
+    #[test]
+    fn test_author_parse() {
+        let author = Author::parse(" Jane Doe <jane@example.com> ").unwrap();
+        assert_eq!((author.name.as_str(), author.email.as_str()), ("Jane Doe", "jane@example.com"));
+        assert_eq!(Author::parse("Jane").unwrap().email, "");
+        assert_eq!(Author::parse("  "), None);
+    }
+
+    #[test]
+    fn test_contributors_dedupe_and_exclude_bots() {
+        let entries = [
+            entry("Zoe <zoe@example.com>", "feat: a\n\nCo-authored-by: Jane Doe <JANE@example.com>\nCo-authored-by: Sam"),
+            entry("dependabot[bot] <49699333+dependabot[bot]@users.noreply.github.com>", "chore: bump"),
+            entry("jane <jane@example.com>", "fix: b"),
+            entry("Renovate <bot@renovateapp.com>", "chore: lock"),
+        ];
+        assert_eq!(entries[0].authors().len(), 3);
+
+        let config = ContributorsConfig::default();
+        let names: Vec<String> = contributors(&entries, &config).into_iter().map(|a| a.name).collect();
+        assert_eq!(names, ["Jane Doe", "Renovate", "Sam", "Zoe"]);
+
+        let config = ContributorsConfig { exclude: Some(vec!["*@renovateapp.com".to_string()]), ..config };
+        let names: Vec<String> = contributors(&entries, &config).into_iter().map(|a| a.name).collect();
+        assert_eq!(names, ["dependabot[bot]", "Jane Doe", "Sam", "Zoe"]);
+    }
+}
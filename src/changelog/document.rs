@@ -0,0 +1,515 @@
+use std::fmt;
+
+use regex::Regex;
+use semver::Version;
+
+/// A parsed CHANGELOG.md: the text before the first version heading, one [`Release`] per version heading and the
+/// link-reference definitions at the end of the file.
+///
+/// Everything convy does not edit is written back byte-for-byte, including CRLF line endings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Changelog {
+    newline: &'static str,
+    pub preamble: String,
+    pub releases: Vec<Release>,
+    pub references: Vec<Reference>,
+}
+
+/// One `## [label] - date` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub label: String,
+    pub date: Option<String>,
+    pub linked: bool, // Written as `## [label](url)`
+    pub line: usize,  // 1-based line of the heading; 0 for sections added since parsing
+    heading: Option<String>, // The original heading line, until the section is edited
+    pub body: String,
+}
+
+/// A `### Title` subsection of a release and its list entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subsection {
+    pub title: String,
+    pub line: usize,
+    pub entries: Vec<String>,
+}
+
+/// A `[label]: url` definition from the block at the end of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub label: String,
+    pub url: String,
+    pub line: usize,
+    raw: Option<String>, // The original line, until the url is edited
+    after: String,       // Blank lines following the definition
+}
+
+/// Marks where the generated part of `[Unreleased]` starts; text outside the markers is written by hand and kept.
+pub const GENERATED_START: &str = "<!-- convy:start -->";
+/// Marks where the generated part of `[Unreleased]` ends.
+pub const GENERATED_END: &str = "<!-- convy:end -->";
+
+// A version heading: `## [label]`, optionally linked, or a bare semantic version such as `## 1.2.0` or `## v1.2.0-rc.1`.
+// Other `## ` headings are prose and belong to the section above them.
+fn heading_re() -> Regex {
+    Regex::new(
+        r"^##[ \t]+(?:\[([^\]]+)\](\([^)]*\))?|(v?\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?))(?:[ \t]+-[ \t]+(\S+))?(?:[ \t]|$)",
+    )
+    .unwrap()
+}
+
+// The opening of a fenced code block: three or more backticks or tildes, returned as the run that closes it
+fn fence(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let marker = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let run = &line[..line.len() - line.trim_start_matches(marker).len()];
+    (run.len() >= 3).then_some(run)
+}
+
+fn reference_re() -> Regex {
+    Regex::new(r"^\[([^\]]+)\]:[ \t]*(\S+)").unwrap()
+}
+
+fn is_unreleased(label: &str) -> bool {
+    label.eq_ignore_ascii_case("unreleased")
+}
+
+impl Changelog {
+    pub fn parse(content: &str) -> Changelog {
+        let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let heading = heading_re();
+        let reference = reference_re();
+
+        // The reference block: the trailing run of definitions and blank lines, starting at a definition
+        let mut block_start = lines.len();
+        for (i, line) in lines.iter().enumerate().rev() {
+            let text = line.trim_end();
+            if reference.is_match(text) {
+                block_start = i;
+            } else if !text.is_empty() {
+                break;
+            }
+        }
+
+        let mut changelog = Changelog { newline, preamble: String::new(), releases: Vec::new(), references: Vec::new() };
+        // The fence of the code block the current line is in; headings inside code blocks are just text
+        let mut open_fence: Option<&str> = None;
+        for (i, line) in lines[..block_start].iter().enumerate() {
+            let captures = match open_fence {
+                Some(open) => {
+                    let closing = line.trim();
+                    if closing.starts_with(open) && closing.trim_start_matches(open.chars().next().unwrap()).is_empty() {
+                        open_fence = None;
+                    }
+                    None
+                }
+                None => {
+                    open_fence = fence(line);
+                    heading.captures(line.trim_end())
+                }
+            };
+            match captures {
+                Some(caps) => changelog.releases.push(Release {
+                    label: caps.get(1).or(caps.get(3)).map_or("", |m| m.as_str()).to_string(),
+                    date: caps.get(4).map(|m| m.as_str().to_string()),
+                    linked: caps.get(2).is_some(),
+                    line: i + 1,
+                    heading: Some(line.to_string()),
+                    body: String::new(),
+                }),
+                None => match changelog.releases.last_mut() {
+                    Some(release) => release.body.push_str(line),
+                    None => changelog.preamble.push_str(line),
+                },
+            }
+        }
+        for (i, line) in lines.iter().enumerate().skip(block_start) {
+            match reference.captures(line.trim_end()) {
+                Some(caps) => changelog.references.push(Reference {
+                    label: caps[1].to_string(),
+                    url: caps[2].to_string(),
+                    line: i + 1,
+                    raw: Some(line.to_string()),
+                    after: String::new(),
+                }),
+                None => changelog.references.last_mut().unwrap().after.push_str(line),
+            }
+        }
+        changelog
+    }
+
+    /// The line ending used by the file: `"\r\n"` or `"\n"`.
+    pub fn newline(&self) -> &'static str {
+        self.newline
+    }
+
+    /// The `[Unreleased]` section, matched case-insensitively.
+    pub fn unreleased(&self) -> Option<&Release> {
+        self.releases.iter().find(|r| is_unreleased(&r.label))
+    }
+
+    /// The section for `label`.
+    pub fn release(&self, label: &str) -> Option<&Release> {
+        self.releases.iter().find(|r| r.label == label)
+    }
+
+    /// The `[Unreleased]` section, added above the first release if the file has none.
+    pub fn unreleased_mut(&mut self) -> &mut Release {
+        let index = match self.releases.iter().position(|r| is_unreleased(&r.label)) {
+            Some(index) => index,
+            None => {
+                if !self.preamble.is_empty() && !self.preamble.ends_with(&self.newline.repeat(2)) {
+                    self.preamble = format!("{}{}", self.preamble.trim_end(), self.newline.repeat(2));
+                }
+                self.releases.insert(0, Release::new("Unreleased", None, String::new()));
+                0
+            }
+        };
+        &mut self.releases[index]
+    }
+
+    /// Replaces the body of `[Unreleased]`, adding the section if needed. `body` may use either line ending.
+    pub fn set_unreleased_body(&mut self, body: &str) {
+        let body = self.convert(body);
+        self.unreleased_mut().body = body;
+    }
+
+    /// Replaces the generated part of `[Unreleased]`, between [`GENERATED_START`] and [`GENERATED_END`], keeping any
+    /// hand-written notes around it. Without markers, entries the generated text also has are dropped from the existing
+    /// body, so a changelog written before the markers existed does not list them twice; what is left is kept as
+    /// hand-written notes above the marked block.
+    pub fn set_unreleased_generated(&mut self, generated: &str) {
+        let newline = self.newline;
+        let generated = self.convert(generated.trim_matches(['\r', '\n']));
+        let block = match generated.is_empty() {
+            true => format!("{}{}{}", GENERATED_START, newline, GENERATED_END),
+            false => format!("{}{}{}{}{}", GENERATED_START, newline, generated, newline, GENERATED_END),
+        };
+
+        let release = self.unreleased_mut();
+        match (release.body.find(GENERATED_START), release.body.find(GENERATED_END)) {
+            (Some(start), Some(end)) if start < end => release.body.replace_range(start..end + GENERATED_END.len(), &block),
+            _ => {
+                let body = without_entries_of(&release.body, &generated);
+                let notes = body.trim_matches(['\r', '\n']);
+                release.body = match notes.is_empty() {
+                    true => format!("{}{}{}{}", newline, block, newline, newline),
+                    false => format!("{0}{1}{0}{0}{2}{0}{0}", newline, notes, block),
+                };
+            }
+        }
+    }
+
+    /// Turns `[Unreleased]` into `[label] - date` and opens a new empty `[Unreleased]` above it. The released section
+    /// keeps its hand-written notes but loses the generated-content markers.
+    pub fn promote_unreleased(&mut self, label: &str, date: &str) -> Result<(), String> {
+        let index = self
+            .releases
+            .iter()
+            .position(|r| is_unreleased(&r.label))
+            .ok_or("Could not find '## [Unreleased]' section to release.")?;
+        let release = &mut self.releases[index];
+        release.body = release
+            .body
+            .split_inclusive('\n')
+            .filter(|line| !matches!(line.trim(), GENERATED_START | GENERATED_END))
+            .collect();
+        release.label = label.to_string();
+        release.date = Some(date.to_string());
+        release.linked = false;
+        release.heading = None;
+        self.releases.insert(index, Release::new("Unreleased", None, self.newline.to_string()));
+        Ok(())
+    }
+
+    /// Drops the sections whose label matches, together with their link references. Returns the dropped labels.
+    pub fn remove_releases(&mut self, matches: impl Fn(&str) -> bool) -> Vec<String> {
+        let removed: Vec<String> = self.releases.iter().filter(|r| matches(&r.label)).map(|r| r.label.clone()).collect();
+        self.releases.retain(|r| !matches(&r.label));
+        self.remove_references(|label| removed.iter().any(|r| r == label));
+        removed
+    }
+
+    // Removes definitions, keeping the blank lines that followed them
+    fn remove_references(&mut self, matches: impl Fn(&str) -> bool) {
+        let mut kept: Vec<Reference> = Vec::new();
+        for reference in self.references.drain(..) {
+            if !matches(&reference.label) {
+                kept.push(reference);
+            } else if let Some(previous) = kept.last_mut() {
+                previous.after.push_str(&reference.after);
+            }
+        }
+        self.references = kept;
+    }
+
+    /// The url of a link-reference definition.
+    pub fn reference(&self, label: &str) -> Option<&str> {
+        self.references.iter().find(|r| r.label.eq_ignore_ascii_case(label)).map(|r| r.url.as_str())
+    }
+
+    /// Adds or updates a Keep a Changelog link-reference definition (`[label]: url`).
+    ///
+    /// New definitions go directly below `[Unreleased]` so the block stays newest first, or at the end of the file.
+    pub fn set_reference(&mut self, label: &str, url: &str) {
+        if let Some(existing) = self.references.iter_mut().find(|r| r.label.eq_ignore_ascii_case(label)) {
+            if existing.url != url {
+                existing.url = url.to_string();
+                existing.raw = None;
+            }
+            return;
+        }
+
+        let reference = Reference { label: label.to_string(), url: url.to_string(), line: 0, raw: None, after: String::new() };
+        match self.references.iter().position(|r| is_unreleased(&r.label)) {
+            Some(index) => {
+                // The new line goes between Unreleased and whatever followed it
+                let after = std::mem::take(&mut self.references[index].after);
+                self.references.insert(index + 1, Reference { after, ..reference });
+            }
+            None if self.references.is_empty() => {
+                // Separate the block from the text above it by one blank line
+                let newline = self.newline;
+                match self.releases.last_mut() {
+                    // Only the heading remains; a blank line is all that is needed
+                    Some(release) if release.body.trim().is_empty() => release.body = newline.to_string(),
+                    Some(release) => release.body = format!("{}{}{}", release.body.trim_end(), newline, newline),
+                    None if !self.preamble.is_empty() => {
+                        self.preamble = format!("{}{}{}", self.preamble.trim_end(), newline, newline)
+                    }
+                    None => {}
+                }
+                self.references.push(reference);
+            }
+            None => self.references.push(reference),
+        }
+    }
+
+    // Converts `\n` line endings to the ones of the file
+    fn convert(&self, text: &str) -> String {
+        if self.newline == "\n" {
+            text.to_string()
+        } else {
+            text.replace("\r\n", "\n").replace('\n', self.newline)
+        }
+    }
+}
+
+// Drops the list entries under `###` headings that `generated` lists as well, then the headings left empty
+fn without_entries_of(body: &str, generated: &str) -> String {
+    let generated: Vec<&str> = generated.lines().filter_map(list_entry).collect();
+    let mut kept = String::new();
+    let mut subsection: Option<(&str, Vec<&str>)> = None;
+    let flush = |kept: &mut String, subsection: Option<(&str, Vec<&str>)>| {
+        if let Some((heading, lines)) = subsection.filter(|(_, lines)| lines.iter().any(|l| !l.trim().is_empty())) {
+            kept.push_str(heading);
+            kept.extend(lines);
+        }
+    };
+    for line in body.split_inclusive('\n') {
+        if line.starts_with("### ") {
+            flush(&mut kept, subsection.replace((line, Vec::new())));
+        } else if let Some((_, lines)) = subsection.as_mut() {
+            if !list_entry(line).is_some_and(|entry| generated.contains(&entry)) {
+                lines.push(line);
+            }
+        } else {
+            kept.push_str(line);
+        }
+    }
+    flush(&mut kept, subsection);
+    kept
+}
+
+// The text of a `- ` or `* ` list entry
+fn list_entry(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).map(str::trim)
+}
+
+impl fmt::Display for Changelog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.preamble)?;
+        for release in &self.releases {
+            match &release.heading {
+                Some(heading) => f.write_str(heading)?,
+                None => {
+                    write!(f, "## [{}]", release.label)?;
+                    if let Some(date) = &release.date {
+                        write!(f, " - {}", date)?;
+                    }
+                    f.write_str(self.newline)?;
+                }
+            }
+            f.write_str(&release.body)?;
+        }
+        for reference in &self.references {
+            match &reference.raw {
+                Some(raw) => f.write_str(raw)?,
+                None => write!(f, "[{}]: {}{}", reference.label, reference.url, self.newline)?,
+            }
+            f.write_str(&reference.after)?;
+        }
+        Ok(())
+    }
+}
+
+impl Release {
+    fn new(label: &str, date: Option<&str>, body: String) -> Release {
+        Release {
+            label: label.to_string(),
+            date: date.map(str::to_string),
+            linked: false,
+            line: 0,
+            heading: None,
+            body,
+        }
+    }
+
+    pub fn is_unreleased(&self) -> bool {
+        is_unreleased(&self.label)
+    }
+
+    /// The semantic version of the label, if it is one.
+    pub fn version(&self) -> Option<Version> {
+        Version::parse(self.label.trim_start_matches('v')).ok()
+    }
+
+    /// The `### ` subsections of the body, with the `-` / `*` list entries under each.
+    pub fn subsections(&self) -> Vec<Subsection> {
+        let mut subsections: Vec<Subsection> = Vec::new();
+        for (i, line) in self.body.lines().enumerate() {
+            let line = line.trim_end();
+            if let Some(title) = line.strip_prefix("### ") {
+                subsections.push(Subsection {
+                    title: title.trim().to_string(),
+                    line: if self.line == 0 { 0 } else { self.line + 1 + i },
+                    entries: Vec::new(),
+                });
+            } else if let Some(entry) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+                if let Some(subsection) = subsections.last_mut() {
+                    subsection.entries.push(entry.to_string());
+                }
+            }
+        }
+        subsections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = "# Changelog\n\nIntro with ## [Unreleased] inside text.\n\n## [Unreleased] \n\n### Features\n- a\n\n## [1.0.0](https://x/tag/v1) - 2024-01-01\n### Bug Fixes\n* b\n\n[Unreleased]: https://x/compare/v1...HEAD\n\n[1.0.0]: https://x/tag/v1\n";
+
+    #[test]
+    fn test_parse_and_round_trip() {
+        let changelog = Changelog::parse(CHANGELOG);
+        assert_eq!(changelog.to_string(), CHANGELOG);
+        let labels: Vec<&str> = changelog.releases.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["Unreleased", "1.0.0"]);
+        let release = changelog.release("1.0.0").unwrap();
+        assert_eq!((release.line, release.linked, release.date.as_deref()), (10, true, Some("2024-01-01")));
+        assert_eq!(release.subsections(), [Subsection { title: "Bug Fixes".to_string(), line: 11, entries: vec!["b".to_string()] }]);
+        assert_eq!(changelog.reference("1.0.0"), Some("https://x/tag/v1"));
+
+        let crlf = CHANGELOG.replace('\n', "\r\n");
+        assert_eq!(Changelog::parse(&crlf).to_string(), crlf);
+    }
+
+    #[test]
+    fn test_prose_headings_and_code_blocks_are_not_sections() {
+        let content = "# Changelog\n\n## Notes\n\nSee below.\n\n## [Unreleased]\n\n```md\n## [0.9.0] - 2020-01-01\n## 0.8.0\n```\n\n~~~~\n```\n## [0.7.0]\n~~~~\n\n## v1.0.0-rc.1 - 2024-01-01 [YANKED]\n\n## 1.0.0beta\n\n## Upgrading to 1.0\n- a\n";
+        let changelog = Changelog::parse(content);
+        let labels: Vec<&str> = changelog.releases.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, ["Unreleased", "v1.0.0-rc.1"]);
+        assert_eq!(changelog.preamble, "# Changelog\n\n## Notes\n\nSee below.\n\n");
+        assert!(changelog.unreleased().unwrap().body.contains("## [0.9.0]"));
+        assert_eq!(changelog.releases[1].date.as_deref(), Some("2024-01-01"));
+        assert!(changelog.releases[1].body.contains("## 1.0.0beta\n\n## Upgrading to 1.0"));
+        assert_eq!(changelog.to_string(), content);
+    }
+
+    #[test]
+    fn test_edits_keep_the_rest() {
+        let mut changelog = Changelog::parse(&CHANGELOG.replace('\n', "\r\n"));
+        changelog.set_unreleased_body("\n### Features\n- c\n\n");
+        changelog.promote_unreleased("1.1.0", "2024-02-01").unwrap();
+        changelog.set_reference("Unreleased", "https://x/compare/v1.1...HEAD");
+        changelog.set_reference("1.1.0", "https://x/compare/v1...v1.1");
+        let expected = "# Changelog\n\nIntro with ## [Unreleased] inside text.\n\n## [Unreleased]\n\n## [1.1.0] - 2024-02-01\n\n### Features\n- c\n\n## [1.0.0](https://x/tag/v1) - 2024-01-01\n### Bug Fixes\n* b\n\n[Unreleased]: https://x/compare/v1.1...HEAD\n[1.1.0]: https://x/compare/v1...v1.1\n\n[1.0.0]: https://x/tag/v1\n";
+        assert_eq!(changelog.to_string(), expected.replace('\n', "\r\n"));
+
+        let removed = changelog.remove_releases(|label| label == "1.1.0");
+        assert_eq!(removed, ["1.1.0"]);
+        assert!(!changelog.to_string().contains("1.1.0"));
+    }
+
+    #[test]
+    fn test_generated_markers_keep_hand_written_notes() {
+        let mut changelog = Changelog::parse("## [Unreleased]\n\n## [1.0.0] - 2024-01-01\n");
+        changelog.set_unreleased_generated("\n### Features\n- a\n\n");
+        assert_eq!(
+            changelog.unreleased().unwrap().body,
+            "\n<!-- convy:start -->\n### Features\n- a\n<!-- convy:end -->\n\n"
+        );
+
+        let edited = changelog.to_string().replace("\n<!-- convy:start -->", "\nUpgrade note.\n\n<!-- convy:start -->");
+        let mut changelog = Changelog::parse(&edited);
+        changelog.set_unreleased_generated("\n### Features\n- a\n- b\n\n");
+        let once = changelog.to_string();
+        changelog.set_unreleased_generated("\n### Features\n- a\n- b\n\n");
+        assert_eq!(changelog.to_string(), once);
+        assert!(once.starts_with("## [Unreleased]\n\nUpgrade note.\n\n<!-- convy:start -->\n### Features\n- a\n- b\n"));
+
+        changelog.promote_unreleased("1.1.0", "2024-02-01").unwrap();
+        assert_eq!(changelog.release("1.1.0").unwrap().body, "\nUpgrade note.\n\n### Features\n- a\n- b\n\n");
+    }
+
+    #[test]
+    fn test_first_generation_keeps_notes_written_without_markers() {
+        let mut changelog = Changelog::parse("## [Unreleased]\r\n\r\nHighlight: faster startup.\r\n\r\n## [1.0.0] - 2024-01-01\r\n");
+        changelog.set_unreleased_generated("\n### Features\n- a\n\n");
+        assert_eq!(
+            changelog.unreleased().unwrap().body,
+            "\r\nHighlight: faster startup.\r\n\r\n<!-- convy:start -->\r\n### Features\r\n- a\r\n<!-- convy:end -->\r\n\r\n"
+        );
+        let once = changelog.to_string();
+        changelog.set_unreleased_generated("\n### Features\n- a\n\n");
+        assert_eq!(changelog.to_string(), once);
+    }
+
+    #[test]
+    fn test_first_generation_drops_entries_written_by_earlier_generations() {
+        let before = "## [Unreleased]\n\n### Features\n- a (1234567)\n\n### Bug Fixes\n- b (89abcde)\n- hand-written fix\n\nA note.\n\n";
+        let mut changelog = Changelog::parse(before);
+        changelog.set_unreleased_generated("\n### Features\n- a (1234567)\n- c (fedcba9)\n\n### Bug Fixes\n- b (89abcde)\n\n");
+        assert_eq!(
+            changelog.unreleased().unwrap().body,
+            "\n### Bug Fixes\n- hand-written fix\n\nA note.\n\n<!-- convy:start -->\n### Features\n- a (1234567)\n- c (fedcba9)\n\n### Bug Fixes\n- b (89abcde)\n<!-- convy:end -->\n\n"
+        );
+    }
+
+    #[test]
+    fn test_set_reference() {
+        let mut changelog = Changelog::parse("## [Unreleased]\n\n[Unreleased]: https://x/compare/v1...HEAD\n[1.0.0]: https://x/tag/v1\n");
+        changelog.set_reference("Unreleased", "https://x/compare/v2...HEAD");
+        changelog.set_reference("2.0.0", "https://x/compare/v1...v2");
+        assert_eq!(
+            changelog.to_string(),
+            "## [Unreleased]\n\n[Unreleased]: https://x/compare/v2...HEAD\n[2.0.0]: https://x/compare/v1...v2\n[1.0.0]: https://x/tag/v1\n"
+        );
+
+        let mut changelog = Changelog::parse("# Changelog\n");
+        changelog.set_reference("Unreleased", "u");
+        assert_eq!(changelog.to_string(), "# Changelog\n\n[Unreleased]: u\n");
+    }
+
+    #[test]
+    fn test_missing_unreleased_is_added() {
+        let mut changelog = Changelog::parse("# Changelog\n## [1.0.0] - 2024-01-01\n- a\n");
+        changelog.set_unreleased_body("\n- b\n\n");
+        assert_eq!(changelog.to_string(), "# Changelog\n\n## [Unreleased]\n\n- b\n\n## [1.0.0] - 2024-01-01\n- a\n");
+    }
+}
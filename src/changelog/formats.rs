@@ -0,0 +1,323 @@
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+
+use super::links::Links;
+use super::template::{self, Context};
+use super::{Entry, ReleaseNotes};
+
+/// The output formats of `convy changelog generate`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The configured changelog template.
+    #[default]
+    Markdown,
+    Json,
+    Html,
+    AsciiDoc,
+    Rst,
+    /// `debian/changelog` stanzas.
+    Debian,
+    /// RPM `%changelog` entries.
+    Rpm,
+}
+
+/// The package a distribution changelog describes, with every default already applied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Packaging {
+    pub name: String,
+    pub maintainer: String,
+    pub distribution: String,
+    pub urgency: String,
+    pub revision: String,
+}
+
+/// One `<section>` per version, with its own heading; entries and titles are HTML-escaped.
+pub const HTML_TEMPLATE: &str = r##"{% macro link(text, url) %}{% if url %}<a href="{{ url }}">{{ text }}</a>{% else %}{{ text }}{% endif %}{% endmacro %}
+{% macro entry(commit) %}
+{% if commit.scope %}<strong>{{ commit.scope }}</strong>: {% endif %}{{ commit.subject }} ({{ link(commit.short_hash, commit.url) }})
+{%- for issue in commit.issues %}, closes {{ link("#" ~ issue.id, issue.url) }}{% endfor %}
+{% endmacro %}
+<section class="release">
+<h2>{{ link(version, compare_url) }}{% if date %} <small>{{ date }}</small>{% endif %}</h2>
+{% if breaking %}
+<h3>⚠ BREAKING CHANGES</h3>
+<ul>
+{% for commit in breaking %}
+<li>{{ entry(commit) }}</li>
+{% endfor %}
+</ul>
+{% endif %}
+{% for section in sections %}
+<h3>{{ section.title }}</h3>
+<ul>
+{% for commit in section.commits %}
+<li>{{ entry(commit) }}</li>
+{% endfor %}
+</ul>
+{% endfor %}
+{% for title, authors in [("Contributors", contributors), ("New Contributors", new_contributors)] if authors %}
+<h3>{{ title }}</h3>
+<ul>
+{% for author in authors %}
+<li>{{ author.name }}</li>
+{% endfor %}
+</ul>
+{% endfor %}
+</section>
+"##;
+
+/// An AsciiDoc level-2 section per version.
+pub const ASCIIDOC_TEMPLATE: &str = r##"{% macro link(text, url) %}{% if url %}{{ url }}[{{ text }}]{% else %}{{ text }}{% endif %}{% endmacro %}
+{% macro entry(commit) %}
+{% if commit.scope %}*{{ commit.scope }}*: {% endif %}{{ commit.subject }} ({{ link(commit.short_hash, commit.url) }})
+{%- for issue in commit.issues %}, closes {{ link("#" ~ issue.id, issue.url) }}{% endfor %}
+{% endmacro %}
+== {{ link(version, compare_url) }}{{ " (" ~ date ~ ")" if date else "" }}
+
+{% if breaking %}
+=== ⚠ BREAKING CHANGES
+
+{% for commit in breaking %}
+* {{ entry(commit) }}
+{% endfor %}
+
+{% endif %}
+{% for section in sections %}
+=== {{ section.title }}
+
+{% for commit in section.commits %}
+* {{ entry(commit) }}
+{% endfor %}
+
+{% endfor %}
+{% for title, authors in [("Contributors", contributors), ("New Contributors", new_contributors)] if authors %}
+=== {{ title }}
+
+{% for author in authors %}
+* {{ author.name }}
+{% endfor %}
+
+{% endfor %}
+"##;
+
+/// A reStructuredText section per version, as used by Sphinx.
+pub const RST_TEMPLATE: &str = r##"{% macro link(text, url) %}{% if url %}`{{ text }} <{{ url }}>`__{% else %}{{ text }}{% endif %}{% endmacro %}
+{% macro entry(commit) %}
+{% if commit.scope %}**{{ commit.scope }}**: {% endif %}{{ commit.subject }} ({{ link(commit.short_hash, commit.url) }})
+{%- for issue in commit.issues %}, closes {{ link("#" ~ issue.id, issue.url) }}{% endfor %}
+{% endmacro %}
+{% set heading = version ~ (" (" ~ date ~ ")" if date else "") %}
+{{ heading }}
+{{ heading|underline("-") }}
+
+{% if breaking %}
+⚠ BREAKING CHANGES
+{{ "⚠ BREAKING CHANGES"|underline("~") }}
+
+{% for commit in breaking %}
+* {{ entry(commit) }}
+{% endfor %}
+
+{% endif %}
+{% for section in sections %}
+{{ section.title }}
+{{ section.title|underline("~") }}
+
+{% for commit in section.commits %}
+* {{ entry(commit) }}
+{% endfor %}
+
+{% endfor %}
+{% for title, authors in [("Contributors", contributors), ("New Contributors", new_contributors)] if authors %}
+{{ title }}
+{{ title|underline("~") }}
+
+{% for author in authors %}
+* {{ author.name }}
+{% endfor %}
+
+{% endfor %}
+"##;
+
+// The JSON document: always a list, so one version and a full history share the same schema
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    releases: Vec<Context<'a>>,
+}
+
+/// Renders versions, newest first, in a standalone format. Markdown uses `markdown_template` and leaves the
+/// version headings to the caller, since it is spliced into CHANGELOG.md; the distribution formats use `packaging`.
+pub fn render(
+    format: Format,
+    releases: &[ReleaseNotes],
+    links: &Links,
+    markdown_template: &str,
+    packaging: &Packaging,
+) -> Result<String, String> {
+    let (name, source) = match format {
+        Format::Json => {
+            let output = JsonOutput { releases: releases.iter().map(|notes| template::context(notes, links)).collect() };
+            return serde_json::to_string_pretty(&output).map_err(|e| e.to_string());
+        }
+        Format::Debian => return Ok(releases.iter().map(|notes| debian(notes, packaging)).collect()),
+        Format::Rpm => return Ok(releases.iter().map(|notes| rpm(notes, packaging)).collect()),
+        Format::Markdown => ("changelog", markdown_template),
+        Format::Html => ("changelog.html", HTML_TEMPLATE),
+        Format::AsciiDoc => ("changelog.adoc", ASCIIDOC_TEMPLATE),
+        Format::Rst => ("changelog.rst", RST_TEMPLATE),
+    };
+
+    let mut out = String::new();
+    for notes in releases {
+        out.push_str(&template::render_as(name, source, notes, links)?);
+    }
+    Ok(out)
+}
+
+// One stanza:
+//
+// convy (1.2.0-1) unstable; urgency=medium
+//
+//   * ui: add dark mode
+//
+//  -- Jane Doe <jane@example.com>  Wed, 01 May 2024 00:00:00 +0000
+fn debian(notes: &ReleaseNotes, packaging: &Packaging) -> String {
+    // Versions not released yet are marked as such, as `dch` does
+    let distribution = if notes.tag.is_some() { packaging.distribution.as_str() } else { "UNRELEASED" };
+    let date = match release_date(notes) {
+        Some(date) => date.format("%a, %d %b %Y 00:00:00 +0000").to_string(),
+        None => Local::now().to_rfc2822(),
+    };
+    let mut out = format!(
+        "{} ({}) {}; urgency={}\n\n",
+        packaging.name,
+        package_version(notes, packaging),
+        distribution,
+        packaging.urgency
+    );
+    for line in entry_lines(notes) {
+        out.push_str(&format!("  * {}\n", line));
+    }
+    out.push_str(&format!("\n -- {}  {}\n\n", packaging.maintainer, date));
+    out
+}
+
+// One entry: `* Wed May 01 2024 Jane Doe <jane@example.com> - 1.2.0-1` followed by `- ` lines
+fn rpm(notes: &ReleaseNotes, packaging: &Packaging) -> String {
+    let date = release_date(notes).unwrap_or_else(|| Local::now().date_naive());
+    let mut out = format!(
+        "* {} {} - {}\n",
+        date.format("%a %b %d %Y"),
+        packaging.maintainer,
+        package_version(notes, packaging)
+    );
+    for line in entry_lines(notes) {
+        out.push_str(&format!("- {}\n", line));
+    }
+    out.push('\n');
+    out
+}
+
+fn release_date(notes: &ReleaseNotes) -> Option<NaiveDate> {
+    notes.date.as_deref().and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+// Pre-releases sort before the final version in both dpkg and rpm only with `~`: 1.2.0~rc.1-1 < 1.2.0-1
+fn package_version(notes: &ReleaseNotes, packaging: &Packaging) -> String {
+    let version = notes.version.as_deref().unwrap_or("0.0.0").replacen('-', "~", 1);
+    format!("{}-{}", version, packaging.revision)
+}
+
+// Breaking changes of hidden types first, then every section in order, with breaking entries flagged
+fn entry_lines(notes: &ReleaseNotes) -> Vec<String> {
+    let line = |entry: &Entry| {
+        let commit = &entry.commit;
+        let breaking = commit.breaking || commit.footers.contains_key("BREAKING-CHANGE");
+        format!(
+            "{}{}{}",
+            if breaking { "BREAKING: " } else { "" },
+            commit.scope.as_ref().map_or(String::new(), |scope| format!("{}: ", scope)),
+            commit.subject
+        )
+    };
+    let listed = |entry: &Entry| notes.sections.iter().any(|s| s.commits.iter().any(|c| c.hash == entry.hash));
+
+    let mut lines: Vec<String> = notes.breaking.iter().filter(|e| !listed(e)).map(line).collect();
+    lines.extend(notes.sections.iter().flat_map(|s| s.commits.iter().map(line)));
+    if lines.is_empty() {
+        lines.push("No user-visible changes.".to_string());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::changelog::contributors::Author;
+    use crate::changelog::{group_commits, ChangelogConfig, Entry};
+
+    fn notes() -> ReleaseNotes {
+        let entries = ["feat(ui): add <dark> mode\n\nCloses #12", "fix: crash"].iter().map(|m| Entry::for_test(m)).collect();
+        let mut notes = group_commits(entries, &ChangelogConfig::default()).0;
+        notes.version = Some("1.2.0".to_string());
+        notes.date = Some("2024-05-01".to_string());
+        notes.tag = Some("v1.2.0".to_string());
+        notes.previous_tag = Some("v1.1.0".to_string());
+        notes
+    }
+
+    #[test]
+    fn test_formats() {
+        let links = Links::new(&ChangelogConfig::default(), Some("https://github.com/acme/widget"));
+        let mut notes = notes();
+        notes.contributors = vec![Author { name: "Jane Doe".to_string(), email: "jane@example.com".to_string() }];
+        let notes = [notes];
+        let render = |format| render(format, &notes, &links, "", &Packaging::default());
+
+        let json: serde_json::Value = serde_json::from_str(&render(Format::Json).unwrap()).unwrap();
+        let release = &json["releases"][0];
+        assert_eq!(release["version"], "1.2.0");
+        assert_eq!(release["date"], "2024-05-01");
+        assert_eq!(release["sections"][0]["title"], "Features");
+        assert_eq!(release["sections"][0]["commits"][0]["scope"], "ui");
+        assert_eq!(release["sections"][0]["commits"][0]["issues"][0]["id"], "12");
+        assert_eq!(release["sections"][0]["commits"][0]["authors"][0]["name"], "Jane Doe");
+        assert_eq!(release["contributors"][0]["email"], "jane@example.com");
+
+        let html = render(Format::Html).unwrap();
+        assert!(html.contains("<h3>Features</h3>"), "{}", html);
+        assert!(html.contains("<h3>Contributors</h3>\n<ul>\n<li>Jane Doe</li>\n</ul>\n</section>"), "{}", html);
+        assert!(html.contains("<li><strong>ui</strong>: add &lt;dark&gt; mode (<a href=\"https:&#x2f;&#x2f;github.com&#x2f;acme&#x2f;widget&#x2f;commit&#x2f;0123456789abcdef\">0123456</a>)"), "{}", html);
+
+        let adoc = render(Format::AsciiDoc).unwrap();
+        assert!(adoc.starts_with("== https://github.com/acme/widget/compare/v1.1.0...v1.2.0[1.2.0] (2024-05-01)\n\n=== Features\n\n* *ui*: add <dark> mode (https://github.com/acme/widget/commit/0123456789abcdef[0123456]"), "{}", adoc);
+
+        assert!(adoc.ends_with("=== Contributors\n\n* Jane Doe\n\n"), "{}", adoc);
+
+        let rst = render(Format::Rst).unwrap();
+        assert!(rst.starts_with("1.2.0 (2024-05-01)\n------------------\n\nFeatures\n~~~~~~~~\n\n* **ui**: add <dark> mode (`0123456 <https://github.com/acme/widget/commit/0123456789abcdef>`__)"), "{}", rst);
+    }
+
+    #[test]
+    fn test_distribution_formats() {
+        let links = Links::new(&ChangelogConfig::default(), None);
+        let mut notes = notes();
+        notes.version = Some("1.2.0-rc.1".to_string());
+        let packaging = Packaging {
+            name: "widget".to_string(),
+            maintainer: "Jane Doe <jane@example.com>".to_string(),
+            distribution: "unstable".to_string(),
+            urgency: "medium".to_string(),
+            revision: "1".to_string(),
+        };
+
+        assert_eq!(
+            render(Format::Debian, &[notes.clone()], &links, "", &packaging).unwrap(),
+            "widget (1.2.0~rc.1-1) unstable; urgency=medium\n\n  * ui: add <dark> mode\n  * crash\n\n -- Jane Doe <jane@example.com>  Wed, 01 May 2024 00:00:00 +0000\n\n"
+        );
+        assert_eq!(
+            render(Format::Rpm, &[notes], &links, "", &packaging).unwrap(),
+            "* Wed May 01 2024 Jane Doe <jane@example.com> - 1.2.0~rc.1-1\n- ui: add <dark> mode\n- crash\n\n"
+        );
+    }
+}
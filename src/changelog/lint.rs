@@ -0,0 +1,213 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use colored::Colorize;
+use semver::Version;
+
+use super::document::Changelog;
+use super::links::Links;
+use super::{version_tags, ChangelogConfig};
+use crate::lexer::Config;
+
+// Subsection names every changelog may use: the breaking-change and contributor headings of the built-in templates
+// and the categories of Keep a Changelog
+const STANDARD_SUBSECTIONS: &[&str] = &[
+    "⚠ BREAKING CHANGES",
+    "Contributors",
+    "New Contributors",
+    "Added",
+    "Changed",
+    "Deprecated",
+    "Removed",
+    "Fixed",
+    "Security",
+];
+
+/// One problem found in a changelog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub line: usize, // 1-based; 0 for problems with the file as a whole
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.message),
+            line => write!(f, "line {}: {}", line, self.message),
+        }
+    }
+}
+
+/// Checks the structure of a changelog. `tags` lists the released versions and their tag names; when given, every
+/// version section must have a tag and every tag a section, a pre-release tag being covered by its final version.
+/// Every section needs a link reference once the file uses them, or as soon as `links` can link versions.
+pub fn lint(
+    content: &str,
+    changelog: &ChangelogConfig,
+    links: &Links,
+    tags: Option<&[(Version, String)]>,
+) -> Vec<Problem> {
+    let document = Changelog::parse(content);
+    let mut problems = Vec::new();
+    let mut problem = |line: usize, message: String| problems.push(Problem { line, message });
+
+    let unreleased: Vec<usize> = document.releases.iter().filter(|r| r.is_unreleased()).map(|r| r.line).collect();
+    match unreleased.first() {
+        None => problem(0, "No [Unreleased] section".to_string()),
+        Some(&line) if !document.releases[0].is_unreleased() => {
+            problem(line, "[Unreleased] must be the first section".to_string())
+        }
+        Some(_) => {}
+    }
+    for &line in unreleased.iter().skip(1) {
+        problem(line, "Duplicate [Unreleased] section".to_string());
+    }
+
+    let allowed: BTreeSet<String> = changelog
+        .sections()
+        .into_iter()
+        .map(|s| s.title)
+        .chain(STANDARD_SUBSECTIONS.iter().map(|s| s.to_string()))
+        .collect();
+    // Before the first release, [Unreleased] has nothing to compare against
+    let released = document.releases.iter().any(|r| !r.is_unreleased());
+    let needs_references =
+        !document.references.is_empty() || (released && links.compare(Some("previous"), "current").is_some());
+    let mut seen: Vec<Version> = Vec::new();
+    let mut previous: Option<Version> = None;
+    for release in &document.releases {
+        for subsection in release.subsections() {
+            if !allowed.contains(&subsection.title) {
+                problem(subsection.line, format!("Unknown subsection '{}'", subsection.title));
+            }
+        }
+        if needs_references && !release.linked && document.reference(&release.label).is_none() {
+            problem(release.line, format!("[{}] has no link reference", release.label));
+        }
+        if release.is_unreleased() {
+            continue;
+        }
+
+        let Some(version) = release.version() else {
+            problem(release.line, format!("'{}' is not a semantic version", release.label));
+            continue;
+        };
+        match &release.date {
+            None => problem(release.line, format!("Version {} has no date", version)),
+            Some(date) if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() => {
+                problem(release.line, format!("Date '{}' of {} is not an ISO 8601 date (YYYY-MM-DD)", date, version))
+            }
+            Some(_) => {}
+        }
+        if seen.contains(&version) {
+            problem(release.line, format!("Version {} appears more than once", version));
+        } else if let Some(previous) = previous.as_ref().filter(|p| *p <= &version) {
+            problem(release.line, format!("Version {} is listed below the older version {}", version, previous));
+        }
+        if let Some(tags) = tags {
+            if !tags.iter().any(|(v, _)| v == &version) {
+                problem(release.line, format!("Version {} has no matching git tag", version));
+            }
+        }
+        seen.push(version.clone());
+        previous = Some(version);
+    }
+
+    // Releasing a final version folds its pre-release sections into it
+    let covered = |version: &Version| {
+        seen.contains(version)
+            || (!version.pre.is_empty()
+                && seen.iter().any(|v| v.pre.is_empty() && (v.major, v.minor, v.patch) == (version.major, version.minor, version.patch)))
+    };
+    for (version, tag) in tags.into_iter().flatten() {
+        if !covered(version) {
+            problem(0, format!("Tag {} has no section in the changelog", tag));
+        }
+    }
+    problems.sort_by_key(|p| p.line);
+    problems
+}
+
+/// Handles `convy changelog lint`: prints each problem with its line and fails if there are any.
+pub fn run_lint(config: &Config, check_tags: bool) -> Result<(), String> {
+    let changelog = config.changelog();
+    let path = changelog.path();
+    if !Path::new(path).exists() {
+        return Err(format!("{} not found. Run `init` first.", path));
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    let tags: Option<Vec<(Version, String)>> = match check_tags {
+        true => Some(version_tags(&config.tag_pattern()?)?.into_iter().map(|(v, tag)| (v, tag.name)).collect()),
+        false => None,
+    };
+    let problems = lint(&content, &changelog, &Links::resolve(&changelog), tags.as_deref());
+    if problems.is_empty() {
+        println!("{} {} looks good", "✔".green(), path);
+        return Ok(());
+    }
+
+    for problem in &problems {
+        match problem.line {
+            0 => eprintln!("{} {}: {}", "✘ Error:".red(), path, problem.message),
+            line => eprintln!("{} {}:{}: {}", "✘ Error:".red(), path, line, problem.message),
+        }
+    }
+    Err(format!("Found {} problem(s) in {}", problems.len(), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_reports_problems_with_lines() {
+        let content = "# Changelog\n\n## [1.1.0] - 2024-02-01\n### Features\n- a\n\n## [Unreleased]\n\n## [1.0.0] - 01/02/2024\n### Misc\n- b\n\n## [1.2.0] - 2024-03-01\n\n## [1.0.0] - 2024-01-01\n\n[Unreleased]: u\n[1.1.0]: a\n[1.0.0]: b\n";
+        let tags = [
+            (Version::new(1, 1, 0), "v1.1.0".to_string()),
+            (Version::parse("1.1.0-rc.1").unwrap(), "v1.1.0-rc.1".to_string()),
+            (Version::new(0, 9, 0), "v0.9.0".to_string()),
+        ];
+        let problems: Vec<String> = lint(content, &ChangelogConfig::default(), &Links::default(), Some(&tags))
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                "Tag v0.9.0 has no section in the changelog",
+                "line 7: [Unreleased] must be the first section",
+                "line 9: Date '01/02/2024' of 1.0.0 is not an ISO 8601 date (YYYY-MM-DD)",
+                "line 9: Version 1.0.0 has no matching git tag",
+                "line 10: Unknown subsection 'Misc'",
+                "line 13: [1.2.0] has no link reference",
+                "line 13: Version 1.2.0 is listed below the older version 1.0.0",
+                "line 13: Version 1.2.0 has no matching git tag",
+                "line 15: Version 1.0.0 appears more than once",
+                "line 15: Version 1.0.0 has no matching git tag",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_accepts_a_clean_changelog() {
+        let content = "# Changelog\r\n\r\n## [Unreleased] \r\n\r\n## [1.0.0](https://x/v1) - 2024-01-01\r\n### Added\r\n- a\r\n";
+        assert!(lint(content, &ChangelogConfig::default(), &Links::default(), None).is_empty());
+    }
+
+    #[test]
+    fn test_lint_requires_references_when_versions_can_be_linked() {
+        let content = "# Changelog\n\n## [Unreleased]\n\n## [1.0.0] - 2024-01-01\n### Added\n- a\n";
+        let changelog = ChangelogConfig::default();
+        assert!(lint(content, &changelog, &Links::default(), None).is_empty());
+
+        let links = Links::new(&changelog, Some("https://github.com/acme/widget"));
+        let problems: Vec<String> = lint(content, &changelog, &links, None).iter().map(|p| p.to_string()).collect();
+        assert_eq!(problems, ["line 3: [Unreleased] has no link reference", "line 5: [1.0.0] has no link reference"]);
+        assert!(lint("# Changelog\n\n## [Unreleased]\n", &changelog, &links, None).is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use regex::Regex;
+
+use super::Entry;
+use crate::lexer::CommitMessage;
+
+/// Rewrites the header git writes for `git revert` (`Revert "feat: x"`) as a conventional `revert: feat: x`, keeping
+/// the rest of the message. Returns `None` for any other message.
+pub fn conventional_message(message: &str) -> Option<String> {
+    let (header, rest) = message.split_once('\n').unwrap_or((message, ""));
+    let quoted = header.trim().strip_prefix("Revert \"")?.strip_suffix('"')?;
+    Some(format!("revert: {}\n{}", quoted, rest).trim_end().to_string())
+}
+
+/// Drops every commit reverted inside `entries` together with its revert, so a change made and undone in the same
+/// range leaves no trace. Entries are newest first, as git lists them; reverting a revert brings the original commit
+/// back. Reverts of commits outside the range are kept.
+pub fn cancel(entries: Vec<Entry>) -> Vec<Entry> {
+    let mut kept = vec![true; entries.len()];
+    // (revert, reverted commit) index pairs that cancelled out
+    let mut undone: Vec<(usize, usize)> = Vec::new();
+    for i in (0..entries.len()).rev() {
+        let Some(target) = reverted(&entries[i], &entries[i + 1..]).map(|j| i + 1 + j) else {
+            continue;
+        };
+        if kept[target] {
+            kept[target] = false;
+            kept[i] = false;
+            undone.push((i, target));
+        } else if let Some(&(_, original)) = undone.iter().find(|(revert, _)| *revert == target) {
+            kept[original] = true;
+            kept[i] = false;
+        }
+    }
+    entries.into_iter().zip(kept).filter_map(|(entry, kept)| kept.then_some(entry)).collect()
+}
+
+// The position in `older` (newest first) of the commit `entry` reverts: the hash from git's "This reverts commit
+// <hash>." line when there is one, otherwise the newest commit whose header the revert's subject quotes
+fn reverted(entry: &Entry, older: &[Entry]) -> Option<usize> {
+    let commit = &entry.commit;
+    if commit.commit_type != "revert" {
+        return None;
+    }
+    let re = Regex::new(r"(?i)This reverts commit ([0-9a-f]{7,40})").unwrap();
+    if let Some(caps) = commit.body.as_deref().and_then(|body| re.captures(body)) {
+        return older.iter().position(|e| e.hash.starts_with(&caps[1]));
+    }
+    let quoted = commit.subject.trim().trim_matches('"');
+    older.iter().position(|e| header(&e.commit) == quoted)
+}
+
+// The first line of a commit message, rebuilt from its parts
+fn header(commit: &CommitMessage) -> String {
+    format!(
+        "{}{}{}: {}",
+        commit.commit_type,
+        commit.scope.as_ref().map_or(String::new(), |scope| format!("({})", scope)),
+        if commit.breaking { "!" } else { "" },
+        commit.subject
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(commits: &[(&str, &str)]) -> Vec<Entry> {
+        commits.iter().map(|(hash, message)| Entry { hash: hash.to_string(), ..Entry::for_test(message) }).collect()
+    }
+
+    fn hashes(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|e| e.hash.as_str()).collect()
+    }
+
+    #[test]
+    fn test_conventional_message() {
+        assert_eq!(
+            conventional_message("Revert \"feat(ui): dark mode\"\n\nThis reverts commit 0123456789abcdef.").unwrap(),
+            "revert: feat(ui): dark mode\n\nThis reverts commit 0123456789abcdef."
+        );
+        assert_eq!(conventional_message("Revert the last change"), None);
+    }
+
+    #[test]
+    fn test_cancel_reverts() {
+        // Newest first: a revert by hash, a revert by header, and a revert of something released earlier
+        let kept = cancel(entries(&[
+            ("e5", "revert: fix: old bug"),
+            ("d4", "revert: feat(ui): dark mode"),
+            ("c3", "fix: crash\n\nThis reverts commit a1b2c3d."),
+            ("b2", "revert: feat: a\n\nThis reverts commit a1b2c3d4e5."),
+            ("a1b2c3d4e5", "feat: a"),
+            ("09", "feat(ui): dark mode"),
+        ]));
+        assert_eq!(hashes(&kept), ["e5", "c3"]);
+
+        let kept = cancel(entries(&[
+            ("c3", "revert: revert: feat: a"),
+            ("b2", "revert: feat: a"),
+            ("a1", "feat: a"),
+        ]));
+        assert_eq!(hashes(&kept), ["a1"]);
+    }
+}
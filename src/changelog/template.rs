@@ -0,0 +1,287 @@
+use minijinja::Environment;
+use regex::Regex;
+use serde::Serialize;
+
+use super::contributors::Author;
+use super::links::Links;
+use super::{Entry, ReleaseNotes};
+use crate::lexer::FooterSeparator;
+
+/// The built-in template, matching the classic conventional-changelog layout.
+pub const DEFAULT_TEMPLATE: &str = r##"{% macro link(text, url) %}{% if url %}[{{ text }}]({{ url }}){% else %}{{ text }}{% endif %}{% endmacro %}
+{% macro entry(commit) %}
+{% if commit.scope %}**{{ commit.scope }}**: {% endif %}{{ commit.subject }} ({{ link(commit.short_hash, commit.url) }})
+{%- for issue in commit.issues %}, closes {{ link("#" ~ issue.id, issue.url) }}{% endfor %}
+{% endmacro %}
+{% if breaking %}
+### ⚠ BREAKING CHANGES
+{% for commit in breaking %}
+- {{ entry(commit) }}
+{% endfor %}
+
+{% endif %}
+{% for section in sections %}
+### {{ section.title }}
+{% for commit in section.commits %}
+- {{ entry(commit) }}
+{% endfor %}
+
+{% endfor %}
+{% if contributors %}
+### Contributors
+{% for author in contributors %}
+- {{ author.name }}
+{% endfor %}
+
+{% endif %}
+{% if new_contributors %}
+### New Contributors
+{% for author in new_contributors %}
+- {{ author.name }}
+{% endfor %}
+
+{% endif %}
+"##;
+
+/// A Keep a Changelog flavoured template: breaking changes are flagged inline instead of getting their own section.
+pub const KEEPACHANGELOG_TEMPLATE: &str = r#"{% for section in sections %}
+### {{ section.title }}
+{% for commit in section.commits %}
+- {% if commit.breaking %}**BREAKING:** {% endif %}{% if commit.scope %}{{ commit.scope }}: {% endif %}{{ commit.subject }} ({% if commit.url %}[{{ commit.short_hash }}]({{ commit.url }}){% else %}{{ commit.short_hash }}{% endif %})
+{% endfor %}
+
+{% endfor %}
+{% if contributors %}
+### Contributors
+{% for author in contributors %}
+- {{ author.name }}
+{% endfor %}
+
+{% endif %}
+{% if new_contributors %}
+### New Contributors
+{% for author in new_contributors %}
+- {{ author.name }}
+{% endfor %}
+
+{% endif %}
+"#;
+
+/// Looks up a built-in template by name.
+pub fn builtin(name: &str) -> Option<&'static str> {
+    match name {
+        "default" => Some(DEFAULT_TEMPLATE),
+        "keepachangelog" => Some(KEEPACHANGELOG_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// What a template sees for one version; also the schema of `generate --format json`.
+#[derive(Serialize)]
+pub struct Context<'a> {
+    version: &'a str,
+    date: Option<&'a str>,
+    tag: Option<&'a str>,
+    previous_tag: Option<&'a str>,
+    compare_url: Option<String>,
+    breaking: Vec<CommitContext<'a>>,
+    sections: Vec<SectionContext<'a>>,
+    contributors: Vec<AuthorContext<'a>>,
+    new_contributors: Vec<AuthorContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct SectionContext<'a> {
+    title: &'a str,
+    commits: Vec<CommitContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct CommitContext<'a> {
+    hash: &'a str,
+    short_hash: &'a str,
+    url: Option<String>,
+    #[serde(rename = "type")]
+    commit_type: &'a str,
+    scope: Option<&'a str>,
+    subject: &'a str,
+    body: Option<&'a str>,
+    footers: Vec<FooterContext<'a>>,
+    issues: Vec<IssueContext>,
+    breaking: bool,
+    author: AuthorContext<'a>,
+    authors: Vec<AuthorContext<'a>>, // The author, then every `Co-authored-by`
+}
+
+#[derive(Serialize)]
+struct FooterContext<'a> {
+    token: &'a str,
+    value: &'a str,
+}
+
+#[derive(Serialize)]
+struct IssueContext {
+    id: String,
+    url: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AuthorContext<'a> {
+    name: &'a str,
+    email: &'a str,
+}
+
+impl<'a> AuthorContext<'a> {
+    fn new(author: &'a Author) -> Self {
+        AuthorContext { name: &author.name, email: &author.email }
+    }
+}
+
+impl<'a> CommitContext<'a> {
+    fn new(entry: &'a Entry, links: &Links) -> Self {
+        let commit = &entry.commit;
+        CommitContext {
+            hash: &entry.hash,
+            short_hash: entry.short_hash(),
+            url: links.commit(&entry.hash),
+            commit_type: &commit.commit_type,
+            scope: commit.scope.as_deref(),
+            subject: &commit.subject,
+            body: commit.body.as_deref(),
+            footers: commit
+                .footers
+                .iter()
+                .map(|f| FooterContext { token: &f.token, value: &f.value })
+                .collect(),
+            issues: issue_refs(entry)
+                .into_iter()
+                .map(|id| IssueContext { url: links.issue(&id), id })
+                .collect(),
+            breaking: commit.breaking || commit.footers.contains_key("BREAKING-CHANGE"),
+            author: AuthorContext { name: &entry.author_name, email: &entry.author_email },
+            authors: std::iter::once(AuthorContext { name: &entry.author_name, email: &entry.author_email })
+                .chain(entry.co_authors.iter().map(AuthorContext::new))
+                .collect(),
+        }
+    }
+}
+
+// Issue numbers referenced from footers such as `Closes #12` or `Refs: #12, #14`
+fn issue_refs(entry: &Entry) -> Vec<String> {
+    let re = Regex::new(r"#(\d+)\b").unwrap();
+    let mut issues: Vec<String> = Vec::new();
+    for footer in entry.commit.footers.iter().filter(|f| !f.is_breaking_change()) {
+        // `Closes #12` keeps the `#` as its separator, leaving just `12` as the value
+        let value = match footer.separator {
+            FooterSeparator::Hash => format!("#{}", footer.value),
+            FooterSeparator::Colon => footer.value.clone(),
+        };
+        for caps in re.captures_iter(&value) {
+            if !issues.iter().any(|i| i == &caps[1]) {
+                issues.push(caps[1].to_string());
+            }
+        }
+    }
+    issues
+}
+
+/// Renders one version's notes with the given template source.
+pub fn render(source: &str, notes: &ReleaseNotes, links: &Links) -> Result<String, String> {
+    render_as("changelog", source, notes, links)
+}
+
+/// Like [`render`], but the template `name` decides auto-escaping: names ending in `.html` escape their output.
+pub fn render_as(name: &str, source: &str, notes: &ReleaseNotes, links: &Links) -> Result<String, String> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    // `{{ title|underline("=") }}` draws a reStructuredText heading underline as long as the title
+    env.add_filter("underline", |text: String, marker: String| marker.repeat(text.chars().count()));
+    env.add_template(name, source)
+        .map_err(|e| format!("Invalid changelog template: {}", e))?;
+
+    env.get_template(name)
+        .and_then(|t| t.render(context(notes, links)))
+        .map_err(|e| format!("Failed to render changelog template: {}", e))
+}
+
+/// The template context for one version's notes.
+pub fn context<'a>(notes: &'a ReleaseNotes, links: &Links) -> Context<'a> {
+    let version = notes.version.as_deref().unwrap_or("Unreleased");
+    let current = notes.tag.as_deref().unwrap_or("HEAD");
+    Context {
+        version,
+        // Unreleased changes without a previous tag have nothing to compare against
+        compare_url: match (&notes.version, &notes.previous_tag) {
+            (None, None) => None,
+            _ => links.compare(notes.previous_tag.as_deref(), current),
+        },
+        date: notes.date.as_deref(),
+        tag: notes.tag.as_deref(),
+        previous_tag: notes.previous_tag.as_deref(),
+        breaking: notes.breaking.iter().map(|e| CommitContext::new(e, links)).collect(),
+        sections: notes
+            .sections
+            .iter()
+            .map(|s| SectionContext {
+                title: &s.title,
+                commits: s.commits.iter().map(|e| CommitContext::new(e, links)).collect(),
+            })
+            .collect(),
+        contributors: notes.contributors.iter().map(AuthorContext::new).collect(),
+        new_contributors: notes.new_contributors.iter().map(AuthorContext::new).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::changelog::{group_commits, ChangelogConfig};
+
+    fn notes(messages: &[&str]) -> ReleaseNotes {
+        let entries = messages.iter().map(|m| Entry::for_test(m)).collect();
+        group_commits(entries, &ChangelogConfig::default()).0
+    }
+
+    #[test]
+    fn test_default_template() {
+        let notes = notes(&["feat(cli)!: new flags\n\nBREAKING CHANGE: old flags removed", "fix: crash"]);
+        let md = render(DEFAULT_TEMPLATE, &notes, &Links::default()).unwrap();
+        assert_eq!(
+            md,
+            "### ⚠ BREAKING CHANGES\n- **cli**: new flags (0123456)\n\n### Features\n- **cli**: new flags (0123456)\n\n### Bug Fixes\n- crash (0123456)\n\n"
+        );
+    }
+
+    #[test]
+    fn test_default_template_links() {
+        let links = Links::new(&ChangelogConfig::default(), Some("git@github.com:acme/widget.git"));
+        let md = render(DEFAULT_TEMPLATE, &notes(&["fix: crash\n\nCloses #12"]), &links).unwrap();
+        assert_eq!(
+            md,
+            "### Bug Fixes\n- crash ([0123456](https://github.com/acme/widget/commit/0123456789abcdef)), closes [#12](https://github.com/acme/widget/issues/12)\n\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_template_context() {
+        let source = "{{ version }}{% for s in sections %}{% for c in s.commits %} {{ c.short_hash }} {{ c.type }} {{ c.author.name }}{% endfor %}{% endfor %}";
+        let md = render(source, &notes(&["fix: crash"]), &Links::default()).unwrap();
+        assert_eq!(md, "Unreleased 0123456 fix Jane Doe");
+    }
+
+    #[test]
+    fn test_contributors_in_default_template() {
+        let mut notes = notes(&["fix: crash"]);
+        let jane = Author { name: "Jane Doe".to_string(), email: "jane@example.com".to_string() };
+        let sam = Author { name: "Sam".to_string(), email: String::new() };
+        notes.contributors = vec![jane, sam.clone()];
+        notes.new_contributors = vec![sam];
+        let md = render(DEFAULT_TEMPLATE, &notes, &Links::default()).unwrap();
+        assert_eq!(
+            md,
+            "### Bug Fixes\n- crash (0123456)\n\n### Contributors\n- Jane Doe\n- Sam\n\n### New Contributors\n- Sam\n\n"
+        );
+    }
+}
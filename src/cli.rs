@@ -1,4 +1,6 @@
-use clap::{clap_derive::Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{clap_derive::Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -14,6 +16,11 @@ pub enum Commands {
     Parse(ParseArgs),
     Changelog(ChangelogArgs),
     Commit(CommitArgs),
+    Config(ConfigArgs),
+    Fix(FixArgs),
+    Version(VersionArgs),
+    /// Bump the version, update the changelog, then commit and tag the release
+    Release(ReleaseArgs),
 }
 
 #[derive(Debug, Args)]
@@ -22,10 +29,22 @@ pub struct CommitArgs {
     pub run: bool,
 }
 
+#[derive(Debug, Args)]
+pub struct FixArgs {
+    #[arg(name = "file", help = "File holding the commit message (e.g. .git/COMMIT_EDITMSG)")]
+    pub file: PathBuf,
+
+    #[arg(long, help = "Only show the diff; fail if the message is not already formatted")]
+    pub check: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct ParseArgs {
     #[arg(name = "commit", help = "Conventional commit message to parse")]
     pub commit: String,
+
+    #[arg(long, help = "Apply unambiguous 'did you mean' suggestions and print the corrected message")]
+    pub fix: bool,
 }
 
 #[derive(Debug, Args)]
@@ -38,12 +57,42 @@ pub struct ChangelogGenerateArgs {
     
     #[arg(short, long, help = "Include all commits from the beginning, ignoring tags")]
     pub all: bool,
+
+    #[arg(long, conflicts_with = "all", help = "Rebuild the whole changelog with one section per version tag")]
+    pub full: bool,
+
+    #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown, conflicts_with = "write", help = "Output format")]
+    pub format: ChangelogFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChangelogFormat {
+    /// The configured changelog template
+    Markdown,
+    /// `{"releases": [...]}` with version, date, sections and commits
+    Json,
+    Html,
+    Asciidoc,
+    Rst,
+    /// debian/changelog stanzas
+    Debian,
+    /// RPM %changelog entries
+    Rpm,
 }
 
 #[derive(Debug, Args)]
 pub struct ChangelogReleaseArgs {
-    #[arg(help = "The version to release (e.g. 1.0.0)")]
-    pub version: String,
+    #[arg(id = "release_version", value_name = "VERSION", help = "The version to release (e.g. 1.0.0); computed from the commits when omitted")]
+    pub version: Option<String>,
+
+    #[arg(long, value_name = "ID", conflicts_with = "release_version", help = "Release the next pre-release with this identifier (e.g. rc)")]
+    pub prerelease: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ChangelogLintArgs {
+    #[arg(long, help = "Skip comparing the version sections with the git tags")]
+    pub no_tags: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -54,13 +103,110 @@ pub enum ChangelogCommands {
     Generate(ChangelogGenerateArgs),
     /// Promote Unreleased changes to a specific version
     Release(ChangelogReleaseArgs),
+    /// Check the changelog's structure, dates, versions and links
+    Lint(ChangelogLintArgs),
 }
 
 #[derive(Debug, Args)]
 pub struct ChangelogArgs {
     #[command(subcommand)]
     pub command: ChangelogCommands,
+
+    #[arg(short, long, global = true, value_name = "NAME", help = "Work on one of the configured [[packages]]")]
+    pub package: Option<String>,
 }
 
 #[derive(Debug, Args)]
 pub struct InitArgs {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReleaseBump {
+    /// Derive the bump from the commits since the last release
+    Auto,
+    Major,
+    Minor,
+    Patch,
+}
+
+#[derive(Debug, Args)]
+pub struct ReleaseArgs {
+    #[arg(long, value_enum, default_value_t = ReleaseBump::Auto, help = "How far to bump the version")]
+    pub bump: ReleaseBump,
+
+    #[arg(long, value_name = "ID", help = "Release a pre-release with this identifier (e.g. rc)")]
+    pub prerelease: Option<String>,
+
+    #[arg(long, help = "Print the planned version, commit and tag without changing anything")]
+    pub dry_run: bool,
+
+    #[arg(short, long, value_name = "NAME", help = "Release only this package (default: every package with changes)")]
+    pub package: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = PlanFormat::Table, help = "How to print the release plan")]
+    pub format: PlanFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PlanFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct VersionNextArgs {
+    #[arg(long, help = "List the commits that decided the bump")]
+    pub explain: bool,
+
+    #[arg(long, value_name = "ID", help = "Compute the next pre-release with this identifier (e.g. rc)")]
+    pub prerelease: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct VersionSetArgs {
+    #[arg(id = "new_version", value_name = "VERSION", help = "The version to write (e.g. 1.4.0)")]
+    pub version: String,
+
+    #[arg(long, help = "List the files that would change without writing them")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VersionCommands {
+    /// Compute the next semantic version from the commits since the last version tag
+    Next(VersionNextArgs),
+    /// Write a version into Cargo.toml, package.json, pyproject.toml, VERSION and configured files
+    Set(VersionSetArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct VersionArgs {
+    #[command(subcommand)]
+    pub command: VersionCommands,
+
+    #[arg(short, long, global = true, value_name = "NAME", help = "Work on one of the configured [[packages]]")]
+    pub package: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigImportArgs {
+    #[arg(long, help = "Path to the commitlint config (defaults to searching the current directory)")]
+    pub from: Option<PathBuf>,
+
+    #[arg(short, long, help = "Overwrite an existing .convy.toml")]
+    pub force: bool,
+
+    #[arg(long, help = "Print the translated config instead of writing it")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommands {
+    /// Translate a commitlint configuration into .convy.toml
+    Import(ConfigImportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
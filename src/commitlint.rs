@@ -0,0 +1,247 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::lexer::{default_config, Config};
+use crate::rules::{Case, CaseRule};
+
+// Config files checked, in the same order commitlint's own loader uses
+const JSON_FILES: &[&str] = &[".commitlintrc", ".commitlintrc.json"];
+const YAML_FILES: &[&str] = &[".commitlintrc.yaml", ".commitlintrc.yml"];
+const CONFIG_FILE: &str = ".convy.toml";
+const JS_FILES: &[&str] = &[
+    ".commitlintrc.js",
+    ".commitlintrc.cjs",
+    ".commitlintrc.mjs",
+    ".commitlintrc.ts",
+    "commitlint.config.js",
+    "commitlint.config.cjs",
+    "commitlint.config.mjs",
+    "commitlint.config.ts",
+];
+
+/// Result of translating a commitlint configuration.
+#[derive(Debug)]
+pub struct ImportReport {
+    pub source: PathBuf,
+    pub config: Config,
+    /// Human readable notes about rules and settings that could not be carried over.
+    pub untranslated: Vec<String>,
+}
+
+/// Imports a commitlint config and writes the translated `.convy.toml`.
+pub fn run_import(from: Option<&Path>, force: bool, dry_run: bool) -> Result<(), String> {
+    let report = match from {
+        Some(path) => import_file(path)?,
+        None => import_from_dir(Path::new("."))?,
+    };
+
+    let toml_str = toml::to_string(&report.config).map_err(|e| e.to_string())?;
+
+    println!("{} Imported {}", "ℹ".blue(), report.source.display());
+    for note in &report.untranslated {
+        println!("{} {}", "!".yellow(), note);
+    }
+
+    if dry_run {
+        println!("\n{}", toml_str);
+        return Ok(());
+    }
+
+    if Path::new(CONFIG_FILE).exists() && !force {
+        return Err(format!("{} already exists. Use --force to overwrite it.", CONFIG_FILE));
+    }
+
+    fs::write(CONFIG_FILE, toml_str).map_err(|e| e.to_string())?;
+    println!("{} Wrote {}", "✔".green(), CONFIG_FILE);
+    Ok(())
+}
+
+/// Locates a commitlint config in `dir` and translates it into a convy `Config`.
+pub fn import_from_dir(dir: &Path) -> Result<ImportReport, String> {
+    for name in JSON_FILES.iter().chain(YAML_FILES) {
+        let path = dir.join(name);
+        if path.is_file() {
+            return import_file(&path);
+        }
+    }
+
+    let package_json = dir.join("package.json");
+    if package_json.is_file() && read_package_json(&package_json)?.is_some() {
+        return import_file(&package_json);
+    }
+
+    if let Some(js) = JS_FILES.iter().find(|name| dir.join(name).is_file()) {
+        return Err(format!(
+            "Found {}, but JavaScript-based commitlint configs are not supported. Convert it to .commitlintrc.json or .commitlintrc.yaml first.",
+            js
+        ));
+    }
+
+    Err("No commitlint configuration found (.commitlintrc.json, .commitlintrc.yaml or a \"commitlint\" key in package.json).".to_string())
+}
+
+/// Translates a single commitlint config file (JSON or YAML, chosen by extension).
+pub fn import_file(path: &Path) -> Result<ImportReport, String> {
+    if path.file_name().and_then(|n| n.to_str()) == Some("package.json") {
+        let section =
+            read_package_json(path)?.ok_or_else(|| format!("{} has no \"commitlint\" key", path.display()))?;
+        let (config, untranslated) = translate(&section)?;
+        return Ok(ImportReport { source: path.to_path_buf(), config, untranslated });
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let is_yaml = matches!(path.extension().and_then(|e| e.to_str()), Some("yaml") | Some("yml"));
+
+    let value: Value = if is_yaml {
+        serde_yaml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+    } else {
+        // An extension-less .commitlintrc may hold either JSON or YAML
+        serde_json::from_str(&content)
+            .or_else(|_| serde_yaml::from_str(&content))
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+    };
+
+    let (config, untranslated) = translate(&value)?;
+    Ok(ImportReport { source: path.to_path_buf(), config, untranslated })
+}
+
+// Returns the "commitlint" section of a package.json, if it has one
+fn read_package_json(path: &Path) -> Result<Option<Value>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut pkg: Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(pkg.get_mut("commitlint").map(Value::take))
+}
+
+/// Translates a parsed commitlint config object into a convy `Config`.
+pub fn translate(value: &Value) -> Result<(Config, Vec<String>), String> {
+    let root = value.as_object().ok_or("commitlint config must be an object")?;
+    let mut config = default_config();
+    let mut untranslated = Vec::new();
+
+    for (key, v) in root {
+        match key.as_str() {
+            "rules" => {}
+            "extends" => untranslated.push(format!(
+                "extends {}: shared configs are not resolved, only rules defined in this file were imported",
+                v
+            )),
+            other => untranslated.push(format!("{}: setting has no convy equivalent", other)),
+        }
+    }
+
+    let Some(rules) = root.get("rules") else {
+        return Ok((config, untranslated));
+    };
+    let rules = rules.as_object().ok_or("commitlint \"rules\" must be an object")?;
+
+    for (name, rule) in rules {
+        let Some(parts) = rule.as_array() else {
+            untranslated.push(format!("{}: expected [level, applicable, value]", name));
+            continue;
+        };
+        let level = parts.first().and_then(Value::as_u64).unwrap_or(0);
+        if level == 0 {
+            // Disabled rules need no translation
+            continue;
+        }
+        let applicable = parts.get(1).and_then(Value::as_str).unwrap_or("always");
+        let value = parts.get(2);
+
+        let translated = match (name.as_str(), applicable) {
+            ("type-enum", "always") => string_list(value).map(|types| config.types = Some(types)),
+            ("scope-enum", "always") => string_list(value).map(|scopes| config.scopes = Some(scopes)),
+            ("header-max-length", "always") => number(value).map(|n| config.max_header_length = Some(n)),
+            ("subject-max-length", "always") => number(value).map(|n| config.max_subject_length = Some(n)),
+            ("body-max-line-length", "always") => number(value).map(|n| config.max_body_line_length = Some(n)),
+            ("type-case", _) => case_rule(applicable, value).map(|r| config.type_case = Some(r)),
+            ("scope-case", _) => case_rule(applicable, value).map(|r| config.scope_case = Some(r)),
+            ("subject-case", _) => case_rule(applicable, value).map(|r| config.subject_case = Some(r)),
+            _ => None,
+        };
+
+        if translated.is_none() {
+            untranslated.push(format!("{} [{}, \"{}\"]: no convy equivalent", name, level, applicable));
+        } else if level == 1 {
+            untranslated.push(format!("{}: warning level is not supported, imported as an error", name));
+        }
+    }
+
+    Ok((config, untranslated))
+}
+
+fn string_list(value: Option<&Value>) -> Option<Vec<String>> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(str::to_string))
+        .collect()
+}
+
+fn number(value: Option<&Value>) -> Option<usize> {
+    value?.as_u64().map(|n| n as usize)
+}
+
+fn case_rule(applicable: &str, value: Option<&Value>) -> Option<CaseRule> {
+    let names = match value? {
+        Value::String(s) => vec![s.clone()],
+        other => string_list(Some(other))?,
+    };
+    let cases = names.iter().map(|n| Case::from_name(n)).collect::<Option<Vec<_>>>()?;
+    match applicable {
+        "always" => Some(CaseRule { always: Some(cases), never: None }),
+        "never" => Some(CaseRule { always: None, never: Some(cases) }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_enum_length_and_case_rules() {
+        let value: Value = serde_json::from_str(
+            r#"{
+                "extends": ["@commitlint/config-conventional"],
+                "rules": {
+                    "type-enum": [2, "always", ["feat", "fix", "release"]],
+                    "scope-enum": [2, "always", ["parser", "cli"]],
+                    "header-max-length": [2, "always", 72],
+                    "subject-case": [2, "never", ["sentence-case", "upper-case"]],
+                    "footer-leading-blank": [1, "always"],
+                    "body-leading-blank": [0, "always"]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let (config, untranslated) = translate(&value).unwrap();
+        assert_eq!(config.types.unwrap(), vec!["feat", "fix", "release"]);
+        assert_eq!(config.scopes.unwrap(), vec!["parser", "cli"]);
+        assert_eq!(config.max_header_length, Some(72));
+        assert_eq!(
+            config.subject_case,
+            Some(CaseRule { always: None, never: Some(vec![Case::SentenceCase, Case::UpperCase]) })
+        );
+        assert_eq!(untranslated.len(), 2);
+        assert!(untranslated[0].starts_with("extends"));
+        assert!(untranslated[1].starts_with("footer-leading-blank"));
+    }
+
+    #[test]
+    fn test_translate_yaml() {
+        let value: Value = serde_yaml::from_str(
+            "rules:\n  type-case: [2, always, lower-case]\n  body-max-line-length: [2, always, 100]\n",
+        )
+        .unwrap();
+
+        let (config, untranslated) = translate(&value).unwrap();
+        assert_eq!(config.type_case, Some(CaseRule { always: Some(vec![Case::LowerCase]), never: None }));
+        assert_eq!(config.max_body_line_length, Some(100));
+        assert!(untranslated.is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::Path;
+
+use colored::Colorize;
+use regex::Regex;
+
+use crate::lexer::{validate_commit_message, Config};
+
+// Header length enforced when neither `max_header_length` nor `max_subject_length` is configured, as in commitlint
+const DEFAULT_MAX_HEADER_LENGTH: usize = 100;
+
+/// A formatted commit message along with a note for every change that was made.
+#[derive(Debug, PartialEq)]
+pub struct FixResult {
+    pub fixed: String,
+    pub changes: Vec<String>,
+    /// Problems that need rewording, such as a header that is too long; the formatter never changes wording.
+    pub unfixable: Vec<String>,
+}
+
+/// Normalizes the mechanical parts of a commit message into canonical Conventional Commits form.
+///
+/// Only formatting is touched: type case, header spacing, a trailing period on the subject, the blank line
+/// between header and body, the `BREAKING CHANGE` token and stray whitespace. Wording is never changed, so a header
+/// over the length limit is reported in [`FixResult::unfixable`] instead.
+/// Once the message parses, it is re-rendered through [`crate::lexer::CommitMessage::render`] so footers are canonical too.
+pub fn format_message(input: &str, config: &Config) -> FixResult {
+    let mut changes = Vec::new();
+    let mut lines: Vec<String> = input.lines().map(|l| l.trim_end().to_string()).collect();
+
+    if lines.iter().zip(input.lines()).any(|(a, b)| a != b) {
+        changes.push("Removed trailing whitespace".to_string());
+    }
+
+    // Drop leading and trailing blank lines
+    while lines.first().is_some_and(|l| l.is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    if let Some(header) = lines.first_mut() {
+        if let Some((fixed_header, header_changes)) = format_header(header) {
+            *header = fixed_header;
+            changes.extend(header_changes);
+        }
+    }
+
+    if lines.len() > 1 && !lines[1].is_empty() {
+        lines.insert(1, String::new());
+        changes.push("Added blank line after the header".to_string());
+    }
+
+    // Footers live in the last paragraph
+    let footer_start = lines.iter().rposition(|l| l.is_empty()).map_or(lines.len(), |i| i + 1);
+    let breaking_re = Regex::new(r"(?i)^breaking[ -]change\s*(:|\s#)\s*").unwrap();
+    for line in lines.iter_mut().skip(footer_start) {
+        if let Some(m) = breaking_re.find(line) {
+            let separator = if m.as_str().contains('#') { " #" } else { ":" };
+            let canonical = format!("BREAKING CHANGE{} ", separator);
+            if m.as_str() != canonical && !m.as_str().starts_with("BREAKING-CHANGE") {
+                *line = format!("{}{}", canonical, &line[m.end()..]);
+                changes.push("Normalized the BREAKING CHANGE footer token".to_string());
+            }
+        }
+    }
+
+    let mut fixed = lines.join("\n");
+    if let Ok(commit) = validate_commit_message(&fixed, config) {
+        let rendered = commit.render();
+        if rendered != fixed {
+            changes.push("Normalized footer formatting".to_string());
+            fixed = rendered;
+        }
+    }
+
+    // Configured limits are reported by validation; without any, a header still has to fit the default
+    let mut unfixable = Vec::new();
+    if config.max_header_length.is_none() && config.max_subject_length.is_none() {
+        let len = fixed.lines().next().unwrap_or_default().chars().count();
+        if len > DEFAULT_MAX_HEADER_LENGTH {
+            unfixable.push(format!("Header is {} characters long, maximum is {}", len, DEFAULT_MAX_HEADER_LENGTH));
+        }
+    }
+    FixResult { fixed, changes, unfixable }
+}
+
+// Rewrites `Type ( scope ) ! :subject.` as `type(scope)!: subject`
+fn format_header(header: &str) -> Option<(String, Vec<String>)> {
+    let re = Regex::new(r"^\s*([A-Za-z][\w-]*)\s*(?:\(\s*([^)]*?)\s*\))?\s*(!)?\s*:\s*(.*?)\s*$").unwrap();
+    let caps = re.captures(header)?;
+
+    let raw_type = &caps[1];
+    let scope = caps.get(2).map(|m| m.as_str()).filter(|s| !s.is_empty());
+    let breaking = caps.get(3).is_some();
+    let mut subject = caps[4].to_string();
+
+    let mut changes = Vec::new();
+    // The header as it would be with only the type and period fixed, to tell whether spacing changed too
+    let mut touched = header.to_string();
+
+    let commit_type = raw_type.to_lowercase();
+    if commit_type != raw_type {
+        touched.replace_range(caps.get(1).unwrap().range(), &commit_type);
+        changes.push(format!("Lowercased type '{}'", raw_type));
+    }
+
+    if subject.ends_with('.') && !subject.ends_with("...") {
+        subject.pop();
+        touched = touched.trim_end().strip_suffix('.').unwrap_or(&touched).to_string();
+        changes.push("Removed trailing period from the subject".to_string());
+    }
+
+    let mut fixed = commit_type;
+    if let Some(scope) = scope {
+        fixed.push_str(&format!("({})", scope));
+    }
+    if breaking {
+        fixed.push('!');
+    }
+    fixed.push_str(": ");
+    fixed.push_str(&subject);
+
+    if fixed != touched {
+        changes.push("Normalized header spacing".to_string());
+    }
+
+    Some((fixed, changes))
+}
+
+/// Renders a line-based diff between two messages, `-` for removed lines and `+` for added ones.
+pub fn line_diff(before: &str, after: &str) -> Vec<String> {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            out.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            out.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+    out.extend(old[i..].iter().map(|l| format!("- {}", l)));
+    out.extend(new[j..].iter().map(|l| format!("+ {}", l)));
+    out
+}
+
+/// Formats the commit message stored in `path`, rewriting the file unless `check` is set.
+///
+/// Returns an error if the message needed changes in `check` mode, or if it still has problems formatting cannot
+/// solve, such as a subject that is too long.
+pub fn run_fix(path: &Path, check: bool, config: &Config) -> Result<(), String> {
+    let original = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let result = format_message(&original, config);
+
+    let changed = result.fixed != original.trim_end_matches('\n');
+    if !changed {
+        println!("{} Commit message is already formatted", "✔".green());
+    } else {
+        for change in &result.changes {
+            println!("{} {}", "ℹ".blue(), change);
+        }
+        for line in line_diff(&original, &result.fixed) {
+            if line.starts_with('-') {
+                println!("{}", line.red());
+            } else if line.starts_with('+') {
+                println!("{}", line.green());
+            } else {
+                println!("{}", line.dimmed());
+            }
+        }
+
+        if !check {
+            fs::write(path, format!("{}\n", result.fixed)).map_err(|e| e.to_string())?;
+            println!("{} Fixed {}", "✔".green(), path.display());
+        }
+    }
+
+    let mut problems = result.unfixable;
+    if let Err(diagnostics) = validate_commit_message(&result.fixed, config) {
+        problems.extend(diagnostics.iter().map(|d| d.to_string()));
+    }
+    for problem in &problems {
+        eprintln!("{} {}", "✘ Error:".red(), problem);
+    }
+
+    if check && changed {
+        return Err("Commit message is not formatted. Run `convy fix` to apply the changes.".to_string());
+    }
+    if !problems.is_empty() {
+        return Err("Commit message still has problems that cannot be fixed automatically".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::default_config;
+
+    #[test]
+    fn test_format_message_normalizes_mechanical_issues() {
+        let message = "Feat(parser) :Add thing.  \nmore detail\n\nbreaking change: old API removed\n\n";
+        let result = format_message(message, &default_config());
+        assert_eq!(
+            result.fixed,
+            "feat(parser): Add thing\n\nmore detail\n\nBREAKING CHANGE: old API removed"
+        );
+        assert!(result.changes.contains(&"Lowercased type 'Feat'".to_string()));
+        assert!(result.changes.contains(&"Added blank line after the header".to_string()));
+    }
+
+    #[test]
+    fn test_format_message_leaves_canonical_message_alone() {
+        let message = "fix(cli)!: handle empty input\n\nBody text.\n\nBREAKING-CHANGE: flags renamed";
+        let result = format_message(message, &default_config());
+        assert_eq!(result.fixed, message);
+        assert!(result.changes.is_empty());
+    }
+
+    #[test]
+    fn test_format_message_renders_canonical_footers() {
+        let message = "feat!: new api\n\nBREAKING CHANGE:old api removed\nRefs:#12";
+        let result = format_message(message, &default_config());
+        assert_eq!(result.fixed, "feat!: new api\n\nBREAKING CHANGE: old api removed\nRefs: #12");
+    }
+
+    #[test]
+    fn test_long_header_is_reported_not_reworded() {
+        let subject = "a".repeat(100);
+        let message = format!("Fix: {}", subject);
+        let result = format_message(&message, &default_config());
+        assert_eq!(result.fixed, format!("fix: {}", subject));
+        assert_eq!(result.unfixable, ["Header is 105 characters long, maximum is 100"]);
+
+        // Configured limits take over from the default and are reported by validation
+        let config = Config { max_header_length: Some(120), ..default_config() };
+        assert!(format_message(&message, &config).unfixable.is_empty());
+
+        let path = std::env::temp_dir().join(format!("convy-fix-{}", std::process::id()));
+        fs::write(&path, format!("fix: {}\n", subject)).unwrap();
+        let result = run_fix(&path, true, &default_config());
+        let _ = fs::remove_file(&path);
+        assert_eq!(result.unwrap_err(), "Commit message still has problems that cannot be fixed automatically");
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("Fix: thing\nbody", "fix: thing\n\nbody");
+        assert_eq!(diff, vec!["- Fix: thing", "+ fix: thing", "+ ", "  body"]);
+    }
+}
@@ -0,0 +1,116 @@
+use std::process::Command;
+
+// Field and record separators for `git log --format`; neither can appear in a commit message
+const FIELD_SEP: char = '\u{1f}';
+const RECORD_SEP: char = '\u{1e}';
+
+/// A commit as read from `git log`, before its message is parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitCommit {
+    pub hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date, `YYYY-MM-DD`.
+    pub date: String,
+    pub message: String,
+    /// Paths changed by the commit, relative to the repository root.
+    pub files: Vec<String>,
+}
+
+impl GitCommit {
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+}
+
+/// Runs git with `args` and returns its stdout, or an error mentioning the failed command.
+pub fn run(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git").args(args).output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// A git configuration value such as `user.name`, if it is set.
+pub fn config_value(key: &str) -> Option<String> {
+    run(&["config", "--get", key]).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Reads the commits in a revision range, newest first, with the files each one touched.
+pub fn log(range: &str) -> Result<Vec<GitCommit>, String> {
+    // `--name-only` prints the file list after the formatted message, so it becomes the last field of the record.
+    // `%aN`/`%aE` apply .mailmap, so one person committing under several identities is reported once.
+    let format = format!("--format={r}%H{f}%aN{f}%aE{f}%as{f}%B{f}", f = FIELD_SEP, r = RECORD_SEP);
+    let raw_log = run(&["log", "--name-only", range, &format]).map_err(|_| "Failed to read git log".to_string())?;
+
+    Ok(raw_log
+        .split(RECORD_SEP)
+        .filter_map(|record| {
+            let mut fields = record.splitn(6, FIELD_SEP);
+            Some(GitCommit {
+                hash: fields.next()?.to_string(),
+                author_name: fields.next()?.to_string(),
+                author_email: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                message: fields.next()?.trim().to_string(),
+                files: fields.next()?.lines().filter(|l| !l.is_empty()).map(str::to_string).collect(),
+            })
+        })
+        .collect())
+}
+
+/// Every author and `Co-authored-by` trailer in the history reachable from `rev`, as `Name <email>` lines. Authors
+/// are already mapped through .mailmap; co-authors are not.
+pub fn identities(rev: &str) -> Result<Vec<String>, String> {
+    let raw = run(&["log", rev, "--format=%aN <%aE>%n%(trailers:key=Co-authored-by,valueonly)"])?;
+    Ok(raw.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+}
+
+/// Maps `Name <email>` contacts through .mailmap, in the same order.
+pub fn check_mailmap(contacts: &[String]) -> Result<Vec<String>, String> {
+    if contacts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut args = vec!["check-mailmap"];
+    args.extend(contacts.iter().map(String::as_str));
+    Ok(run(&args)?.lines().map(str::to_string).collect())
+}
+
+/// The fetch URL of the `origin` remote, if one is configured.
+pub fn remote_url() -> Option<String> {
+    run(&["remote", "get-url", "origin"])
+        .ok()
+        .map(|out| out.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// Whether `path` is excluded by a .gitignore rule.
+pub fn is_ignored(path: &str) -> bool {
+    run(&["check-ignore", "-q", "--", path]).is_ok()
+}
+
+/// A tag and the date it was created (the commit date for lightweight tags).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    /// `YYYY-MM-DD`.
+    pub date: String,
+}
+
+/// Every tag reachable from HEAD, in no particular order.
+pub fn tags() -> Result<Vec<Tag>, String> {
+    let format = format!("--format=%(refname:short){}%(creatordate:short)", FIELD_SEP);
+    let raw = run(&["for-each-ref", "--merged=HEAD", &format, "refs/tags"])?;
+    Ok(raw
+        .lines()
+        .filter_map(|line| {
+            let (name, date) = line.split_once(FIELD_SEP)?;
+            Some(Tag { name: name.to_string(), date: date.to_string() })
+        })
+        .collect())
+}
@@ -1,24 +1,63 @@
 use logos::Logos;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::fmt;
 use std::ops::Range;
 
+use crate::changelog::ChangelogConfig;
+use crate::packages::PackageConfig;
+use crate::rules::{check_body_rules, check_header_rules, CaseRule};
+use crate::suggest::{suggest, Suggestion, WELL_KNOWN_FOOTERS};
+use crate::version::VersionConfig;
+
+// Commit types recognised by the lexer without any configuration
+pub const BUILTIN_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "test", "chore", "perf", "build", "ci", "revert", "merge", "wip",
+];
+
 // Configuration Structure
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Config {
     pub additional_types: Option<Vec<String>>, // Optional additional types
+    pub types: Option<Vec<String>>,            // Optional allowed types (replaces the built-in set)
     pub scopes: Option<Vec<String>>,           // Optional allowed scopes
     pub require_breaking_change_footer: Option<bool>,
     pub emoji: Option<bool>,
+    pub max_header_length: Option<usize>,
+    pub max_subject_length: Option<usize>,
+    pub max_body_line_length: Option<usize>,
+    pub type_case: Option<CaseRule>,
+    pub scope_case: Option<CaseRule>,
+    pub subject_case: Option<CaseRule>,
+    pub changelog: Option<ChangelogConfig>,
+    pub version: Option<VersionConfig>,
+    pub packages: Option<Vec<PackageConfig>>,   // Independently released parts of a monorepo
+}
+
+impl Config {
+    // Types outside the built-in set that the config allows
+    fn is_custom_type(&self, t: &str) -> bool {
+        self.additional_types.iter().chain(self.types.iter()).flatten().any(|c| c == t)
+    }
+
+    /// Every type a commit may use under this config.
+    pub fn known_types(&self) -> Vec<&str> {
+        match &self.types {
+            Some(types) => types.iter().map(String::as_str).collect(),
+            None => BUILTIN_TYPES
+                .iter()
+                .copied()
+                .chain(self.additional_types.iter().flatten().map(String::as_str))
+                .collect(),
+        }
+    }
 }
 
 // Default Configuration
 pub fn default_config() -> Config {
     Config {
-        additional_types: None,
-        scopes: None,
         require_breaking_change_footer: Some(true),
         emoji: Some(false),
+        ..Config::default()
     }
 }
 
@@ -79,11 +118,12 @@ pub enum Token {
 // For this exercise, we'll assume it's `()` as Token does not define a custom Error type.
 pub type LexerError = (); // This should be <Token as Logos>::Error
 
+#[allow(clippy::result_unit_err)]
 pub fn lex_commit_message(input: &str) -> Result<Vec<(Token, Range<usize>)>, LexerError> {
-    let mut lexer = Token::lexer(input).spanned(); // Use .spanned() to get spans
+    let lexer = Token::lexer(input).spanned(); // Use .spanned() to get spans
     let mut tokens_with_spans = Vec::new();
 
-    while let Some(item_tuple) = lexer.next() {
+    for item_tuple in lexer {
         // According to compiler, item_tuple is (Result<Token, ()>, Range<usize>)
         // This is unusual for logos .spanned() unless the token being spanned is itself a Result.
         // This implies Token::lexer(input) produces Iterator<Item=Result<Token, ()>>
@@ -107,175 +147,400 @@ pub fn lex_commit_message(input: &str) -> Result<Vec<(Token, Range<usize>)>, Lex
 }
 
 
-#[derive(Debug)]
+/// How a footer token is separated from its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FooterSeparator {
+    /// `Token: value`
+    Colon,
+    /// `Token #value`
+    Hash,
+}
+
+/// A single `Token: value` or `Token #value` footer, kept exactly as written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footer {
+    pub token: String,
+    pub separator: FooterSeparator,
+    pub value: String,
+}
+
+impl Footer {
+    pub fn new(token: impl Into<String>, value: impl Into<String>) -> Footer {
+        Footer { token: token.into(), separator: FooterSeparator::Colon, value: value.into() }
+    }
+
+    /// `BREAKING CHANGE` and `BREAKING-CHANGE` are synonyms.
+    pub fn is_breaking_change(&self) -> bool {
+        is_breaking_change_token(&self.token)
+    }
+
+    /// The token with both BREAKING CHANGE spellings normalized to `BREAKING-CHANGE`. `token` keeps the spelling
+    /// as written, so rendering round-trips.
+    pub fn key(&self) -> &str {
+        match self.is_breaking_change() {
+            true => "BREAKING-CHANGE",
+            false => &self.token,
+        }
+    }
+
+    // Token comparison, treating the two BREAKING CHANGE spellings as equal
+    fn matches(&self, token: &str) -> bool {
+        self.token == token || (self.is_breaking_change() && is_breaking_change_token(token))
+    }
+}
+
+impl fmt::Display for Footer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.separator {
+            FooterSeparator::Colon => write!(f, "{}: {}", self.token, self.value),
+            FooterSeparator::Hash => write!(f, "{} #{}", self.token, self.value),
+        }
+    }
+}
+
+fn is_breaking_change_token(token: &str) -> bool {
+    let upper = token.to_uppercase();
+    upper == "BREAKING CHANGE" || upper == "BREAKING-CHANGE"
+}
+
+/// The footers of a commit, in the order they were written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Footers(Vec<Footer>);
+
+impl Footers {
+    pub fn new() -> Footers {
+        Footers(Vec::new())
+    }
+
+    pub fn push(&mut self, footer: Footer) {
+        self.0.push(footer);
+    }
+
+    /// Value of the first footer with this token, or of the last one for BREAKING CHANGE, which either spelling finds.
+    pub fn get(&self, token: &str) -> Option<&String> {
+        let mut matching = self.0.iter().filter(|f| f.matches(token));
+        let footer = match is_breaking_change_token(token) {
+            true => matching.next_back(),
+            false => matching.next(),
+        };
+        footer.map(|f| &f.value)
+    }
+
+    pub fn contains_key(&self, token: &str) -> bool {
+        self.0.iter().any(|f| f.matches(token))
+    }
+
+    /// Every footer's [`Footer::key`], in order and with duplicates.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(Footer::key)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Footer> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Footers {
+    type Item = &'a Footer;
+    type IntoIter = std::slice::Iter<'a, Footer>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<Footer> for Footers {
+    fn from_iter<I: IntoIterator<Item = Footer>>(iter: I) -> Footers {
+        Footers(iter.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CommitMessage {
     pub commit_type: String,
     pub scope: Option<String>,
+    /// Set when the header carries a `!` before the colon.
+    pub breaking: bool,
     pub subject: String,
     pub body: Option<String>,
-    pub footers: HashMap<String, String>,
+    pub footers: Footers,
 }
 
+impl CommitMessage {
+    /// The `type(scope)!: subject` line.
+    pub fn header(&self) -> String {
+        let mut header = self.commit_type.clone();
+        if let Some(scope) = &self.scope {
+            header.push('(');
+            header.push_str(scope);
+            header.push(')');
+        }
+        if self.breaking {
+            header.push('!');
+        }
+        header.push_str(": ");
+        header.push_str(&self.subject);
+        header
+    }
+
+    /// Renders the canonical message text: header, blank line, body, blank line, footers in order.
+    ///
+    /// For any message produced by the parser, parsing the rendered text yields the same `CommitMessage`.
+    pub fn render(&self) -> String {
+        let mut msg = self.header();
+        if let Some(body) = &self.body {
+            msg.push_str("\n\n");
+            msg.push_str(body);
+        }
+        if !self.footers.is_empty() {
+            msg.push_str("\n\n");
+            let lines: Vec<String> = self.footers.iter().map(Footer::to_string).collect();
+            msg.push_str(&lines.join("\n"));
+        }
+        msg
+    }
+}
+
+impl fmt::Display for CommitMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+/// A single problem found while validating a commit message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub suggestion: Option<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>) -> Diagnostic {
+        Diagnostic { message: message.into(), suggestion: None }
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<Suggestion>) -> Diagnostic {
+        self.suggestion = suggestion;
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.suggestion {
+            Some(suggestion) => write!(f, "{}. {}", self.message, suggestion),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Parses a commit message, stopping at the first problem.
+///
+/// This is a thin wrapper around [`validate_commit_message`] that only reports the first diagnostic.
 pub fn parse_commit_message(input: &str, config: Config) -> Result<CommitMessage, String> {
-    let tokens_with_spans = lex_commit_message(input).map_err(|_e| "Lexing error".to_string())?;
-    let mut token_iter = tokens_with_spans.into_iter();
+    validate_commit_message(input, &config)
+        .map_err(|diagnostics| diagnostics.into_iter().next().map(|d| d.message).unwrap_or_default())
+}
+
+/// Parses and validates a commit message, collecting every diagnostic in one pass.
+///
+/// Header problems do not stop validation: the parser skips ahead to the header colon so that the subject,
+/// body and footer checks still run and their diagnostics are reported alongside the header ones.
+pub fn validate_commit_message(input: &str, config: &Config) -> Result<CommitMessage, Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
 
     let mut commit_type: Option<String> = None;
     let mut exclamation_mark = false;
     let mut scope: Option<String> = None;
-    // subject will be declared and assigned after header parsing
-    let mut body: Option<String> = None;
-    // footers will be assigned from parse_body_and_footers
-    let mut position = 0; // Tracks current position in the original input string
-
-    // Parse the header line
-    while let Some((token, span)) = token_iter.next() {
-        match token {
-            Token::Type(t) => {
-                commit_type = Some(t);
-                position = span.end;
-            }
-            Token::ExclamationMark => {
-                exclamation_mark = true;
-                position = span.end;
-                // Original code had `span.end + 1` and then a break.
-                // The `+1` might be to skip the `!` itself if it was part of the span.
-                // If `ExclamationMark` token is just `!`, then `span.end` is correct.
-                // Then we need to find the colon.
-                // Let's adjust to look for colon explicitly after this.
-                // The original code broke after `ExclamationMark` IF it was followed by colon.
-                // This logic needs to be a bit more careful.
-                // For now, let's assume `position` is correctly at the end of `!`.
-                // The next token should be Colon or Scope then Colon.
+    let mut colon_end: Option<usize> = None;
+    let mut header_invalid = false;
+
+    // Only the header line is tokenised, so stray characters in the body can't fail the whole message
+    let header_end = input.find('\n').unwrap_or(input.len());
+    let header = &input[..header_end];
+
+    for (token_result, span) in Token::lexer(header).spanned() {
+        match token_result {
+            Ok(Token::Type(t)) => commit_type = Some(t),
+            Ok(Token::Text(t)) if commit_type.is_none() && config.is_custom_type(&t) => commit_type = Some(t),
+            // A word where the type should be: most likely a typo of a known type
+            Ok(Token::Text(t)) | Ok(Token::Tag(t)) if commit_type.is_none() && !header_invalid => {
+                header_invalid = true;
+                diagnostics.push(
+                    Diagnostic::new(format!("Unknown commit type: '{}'", t))
+                        .with_suggestion(suggest(&t, span, config.known_types())),
+                );
             }
-            Token::Scope(s) => {
+            Ok(Token::ExclamationMark) => exclamation_mark = true,
+            Ok(Token::Scope(s)) => {
                 if let Some(allowed_scopes) = &config.scopes {
                     if !allowed_scopes.contains(&s) {
-                        return Err(format!("Invalid scope: '{}'. Allowed scopes: {:?}", s, allowed_scopes));
+                        // The token span includes the parentheses
+                        let inner = span.start + 1..span.end - 1;
+                        diagnostics.push(
+                            Diagnostic::new(format!("Invalid scope: '{}'. Allowed scopes: {:?}", s, allowed_scopes))
+                                .with_suggestion(suggest(&s, inner, allowed_scopes.iter().map(String::as_str))),
+                        );
                     }
                 }
                 scope = Some(s);
-                position = span.end;
             }
-            Token::Colon => {
-                position = span.end;
+            Ok(Token::Colon) => {
+                colon_end = Some(span.end);
                 break; // Stop parsing header after colon
             }
-            Token::Whitespace => {
-                // Whitespace might shift the effective 'position' if not handled carefully
-                // For now, assume critical tokens update position correctly.
-                // If whitespace is the last token before subject, its span.end might be used.
-                position = span.end;
+            Ok(Token::Whitespace) => {}
+            // Report the first bad token only, then keep scanning for the colon to recover
+            Ok(token) if !header_invalid => {
+                header_invalid = true;
+                diagnostics.push(Diagnostic::new(format!("Invalid token in header: {:?} at {:?}", token, span)));
+            }
+            Err(_) if !header_invalid => {
+                header_invalid = true;
+                diagnostics.push(Diagnostic::new(format!("Invalid character in header at {:?}", span)));
             }
-            _ => return Err(format!("Invalid token in header: {:?} at {:?}", token, span)),
+            _ => {}
         }
     }
 
-    // Ensure commit type was found
-    if commit_type.is_none() {
-        return Err("Commit type is missing".to_string());
+    match &commit_type {
+        None if !header_invalid => diagnostics.push(Diagnostic::new("Commit type is missing")),
+        None => {}
+        Some(found_type) => {
+            if let Some(allowed_types) = &config.types {
+                if !allowed_types.contains(found_type) {
+                    let span = header.find(found_type.as_str()).map(|start| start..start + found_type.len());
+                    diagnostics.push(
+                        Diagnostic::new(format!("Invalid type: '{}'. Allowed types: {:?}", found_type, allowed_types))
+                            .with_suggestion(span.and_then(|span| {
+                                suggest(found_type, span, allowed_types.iter().map(String::as_str))
+                            })),
+                    );
+                }
+            }
+        }
     }
 
-    // Parsing subject: Take text from 'position' up to the first newline
-    // This part critically relies on 'position' being the char index after the header colon.
-    let subject_text_slice = input.get(position..).ok_or_else(|| "Invalid position for subject".to_string())?;
-    let subject = if let Some(subject_str) = subject_text_slice.splitn(2, '\n').next() {
-        let s = subject_str.trim().to_string();
-        if s.is_empty() {
-            return Err("Subject is empty".to_string());
+    // Parsing subject: Take the rest of the header line after the colon
+    let subject = match colon_end {
+        Some(end) => {
+            let s = header[end..].trim().to_string();
+            if s.is_empty() {
+                diagnostics.push(Diagnostic::new("Subject is empty"));
+                None
+            } else {
+                Some(s)
+            }
+        }
+        None => {
+            diagnostics.push(Diagnostic::new("Header is missing the ':' separator after the type"));
+            None
         }
-        position += subject_str.len(); // Advance position by length of subject string part
-        Some(s)
-    } else {
-        // This case means subject_text_slice was empty or did not contain \n,
-        // but splitn should still yield one part.
-        // If subject_str is empty here, it means nothing after colon.
-        return Err("Subject is missing or invalid".to_string());
     };
 
-    // Check if there's more content
-    // Extract body and footers
-    let body_and_footers_text = if input.len() > position {
-        // Check if there's a newline immediately after the subject
-        if input[position..].starts_with('\n') {
-            position += 1; // Move past the first newline
-             // If there's another newline (double newline), move past it as well
-            if input[position..].starts_with('\n') {
-                position +=1;
-            }
-            &input[position..]
-        } else {
-            // This case should ideally not happen if subject parsing is correct
-            // and there's content after subject.
-            // However, if it does, consider it as having no body/footers.
-            ""
+    if let (Some(commit_type), Some(subject)) = (&commit_type, &subject) {
+        for violation in check_header_rules(config, header, commit_type, scope.as_deref(), subject) {
+            diagnostics.push(Diagnostic::new(violation));
         }
-    } else {
-        ""
-    };
+    }
 
-    let (parsed_body, parsed_footers) = parse_body_and_footers(body_and_footers_text)?;
-    if !parsed_body.is_empty() {
-        body = Some(parsed_body);
+    // Extract body and footers, skipping the blank line that separates them from the header
+    let mut body_and_footers_text = &input[header_end..];
+    if let Some(rest) = body_and_footers_text.strip_prefix('\n') {
+        body_and_footers_text = rest.strip_prefix('\n').unwrap_or(rest);
     }
-    let mut footers = parsed_footers; // Declare and assign footers here
 
+    let (parsed_body, footers) = parse_body_and_footers(body_and_footers_text);
+    diagnostics.extend(check_footer_tokens(input, &footers));
+    let body = if parsed_body.is_empty() {
+        None
+    } else {
+        for violation in check_body_rules(config, &parsed_body) {
+            diagnostics.push(Diagnostic::new(violation));
+        }
+        Some(parsed_body)
+    };
 
     // Validate BREAKING-CHANGE footer requirements
-    let has_breaking_change_footer = footers.keys().any(|k| k == "BREAKING-CHANGE" || k == "BREAKING CHANGE");
-
-    if config.require_breaking_change_footer.unwrap_or(true) {
-        if exclamation_mark && !has_breaking_change_footer {
-            return Err(
-                "Commit message with '!' in header must include 'BREAKING-CHANGE' or 'BREAKING CHANGE' in footers"
-                    .to_string(),
-            );
-        }
+    let has_breaking_change_footer = footers.iter().any(Footer::is_breaking_change);
+
+    if config.require_breaking_change_footer.unwrap_or(true) && exclamation_mark && !has_breaking_change_footer {
+        diagnostics.push(Diagnostic::new(
+            "Commit message with '!' in header must include 'BREAKING-CHANGE' or 'BREAKING CHANGE' in footers",
+        ));
     }
 
     // This check should always be enforced: if a BREAKING-CHANGE footer is present, '!' must be in the header.
-    // This was previously inside the config block, but it should be independent.
     if has_breaking_change_footer && !exclamation_mark {
-        return Err(
-            "Commit message with 'BREAKING-CHANGE' or 'BREAKING CHANGE' in footers must include '!' in the header"
-                .to_string(),
-        );
+        diagnostics.push(Diagnostic::new(
+            "Commit message with 'BREAKING-CHANGE' or 'BREAKING CHANGE' in footers must include '!' in the header",
+        ));
     }
 
-    // Normalize "BREAKING CHANGE" to "BREAKING-CHANGE"
-    if footers.contains_key("BREAKING CHANGE") {
-        if let Some(value) = footers.remove("BREAKING CHANGE") {
-            footers.insert("BREAKING-CHANGE".to_string(), value);
-        }
+    match (commit_type, subject) {
+        (Some(commit_type), Some(subject)) if diagnostics.is_empty() => Ok(CommitMessage {
+            commit_type,
+            scope,
+            breaking: exclamation_mark,
+            subject,
+            body,
+            footers,
+        }),
+        _ => Err(diagnostics),
     }
+}
 
+// Flags footer tokens that look like misspellings of well-known ones (e.g. `Signed-of-by`)
+fn check_footer_tokens(input: &str, footers: &Footers) -> Vec<Diagnostic> {
+    let mut found: Vec<(usize, Diagnostic)> = footers
+        .iter()
+        .map(|f| f.token.as_str())
+        .filter(|key| !is_well_known_footer(key))
+        .filter_map(|key| {
+            let start = footer_token_offset(input, key)?;
+            let suggestion = suggest(key, start..start + key.len(), WELL_KNOWN_FOOTERS.iter().copied())?;
+            let diagnostic = Diagnostic::new(format!("Footer token '{}' looks misspelled", key))
+                .with_suggestion(Some(suggestion));
+            Some((start, diagnostic))
+        })
+        .collect();
+    found.sort_by_key(|(start, _)| *start);
+    found.into_iter().map(|(_, d)| d).collect()
+}
 
-    if let Some(commit_type) = commit_type {
-        if let Some(subject) = subject {
-            if subject.is_empty() {
-                return Err("Subject is empty".to_string());
-            }
-            Ok(CommitMessage {
-                commit_type,
-                scope,
-                subject,
-                body,
-                footers,
-            })
-        } else {
-            Err("Subject is missing".to_string())
-        }
-    } else {
-        Err("Commit type is missing".to_string())
-    }
+fn is_well_known_footer(key: &str) -> bool {
+    let key = key.to_lowercase();
+    // Short abbreviations such as `Ref` are deliberate, not typos
+    key == "breaking-change"
+        || WELL_KNOWN_FOOTERS.iter().any(|known| {
+            let known = known.to_lowercase();
+            known == key || (key.len() <= 3 && known.starts_with(&key))
+        })
+}
+
+// Byte offset of a footer token in the original message; footers sit at the end, so search backwards
+fn footer_token_offset(input: &str, key: &str) -> Option<usize> {
+    input.rmatch_indices(key).map(|(i, _)| i).find(|&i| {
+        let line_start = input[..i].rfind('\n').map_or(0, |n| n + 1);
+        input[line_start..i].trim().is_empty()
+    })
 }
 
 // Parses the body and footers from the text following the commit subject.
 // Footers are lines at the end of the commit that look like "Token: Value" or "Token # Value".
 // Everything before the footers (if any) is considered the body.
 // Body can have multiple paragraphs separated by blank lines.
-fn parse_body_and_footers(text: &str) -> Result<(String, HashMap<String, String>), String> {
-    let mut footers = HashMap::new();
+fn parse_body_and_footers(text: &str) -> (String, Footers) {
+    // Collected bottom-up, reversed into written order at the end
+    let mut footers: Vec<Footer> = Vec::new();
     let lines: Vec<&str> = text.lines().collect();
     let mut footer_start_index = lines.len();
 
@@ -294,9 +559,10 @@ fn parse_body_and_footers(text: &str) -> Result<(String, HashMap<String, String>
             continue;
         }
 
-        let parts: Option<(String, String)> = {
+        let parts: Option<(String, FooterSeparator, String)> = {
             let mut key_str: Option<String> = None;
             let mut val_str: Option<String> = None;
+            let mut separator = FooterSeparator::Colon;
 
             // Try "Token # Value" first
             if let Some(hash_idx) = trimmed_line.find(" #") {
@@ -307,6 +573,7 @@ fn parse_body_and_footers(text: &str) -> Result<(String, HashMap<String, String>
                     if !pv.is_empty() {
                         key_str = Some(pk.to_string());
                         val_str = Some(pv.to_string());
+                        separator = FooterSeparator::Hash;
                     }
                 }
             }
@@ -330,30 +597,16 @@ fn parse_body_and_footers(text: &str) -> Result<(String, HashMap<String, String>
             }
 
             if let (Some(k), Some(v)) = (key_str, val_str) {
-                Some((k, v))
+                Some((k, separator, v))
             } else {
                 None
             }
         };
 
-        if let Some((key, value)) = parts {
-            // Git trailer convention: only the first of duplicated keys is used.
-            // However, conventional commits might imply overriding or specific handling.
-            // For now, let's mimic git trailers: if key already exists, do not overwrite.
-            // Except for BREAKING-CHANGE, where we might want the last one if multiple are provided (though unusual).
-            // For simplicity, let's use HashMap's default behavior (last one wins for simple inserts)
-            // or decide on a specific strategy.
-            // Conventional commits spec doesn't explicitly state how to handle duplicate footers.
-            // Git's behavior is first-one-wins. Let's stick to that for non-BREAKING CHANGE.
-            let normalized_key = if key.to_uppercase() == "BREAKING CHANGE" {
-                "BREAKING-CHANGE".to_string()
-            } else {
-                key
-            };
-
-            if !footers.contains_key(&normalized_key) || normalized_key == "BREAKING-CHANGE" {
-                 footers.insert(normalized_key, value);
-            }
+        if let Some((token, separator, value)) = parts {
+            // Every footer is kept, duplicates included, so the message can be rendered back as written.
+            // Lookups by token return the first occurrence, except BREAKING CHANGE, where the last one wins.
+            footers.push(Footer { token, separator, value });
             footer_start_index = i;
         } else {
             // This line is not a footer, so everything from here upwards is body.
@@ -361,18 +614,14 @@ fn parse_body_and_footers(text: &str) -> Result<(String, HashMap<String, String>
         }
     }
 
-    // Reverse footers because we collected them bottom-up
-    // No, HashMap doesn't preserve order, so reversing keys isn't meaningful here.
-    // The collection logic ensures the correct `footer_start_index`.
-
     let body_lines = &lines[0..footer_start_index];
     let body = body_lines.join("\n").trim_end_matches('\n').to_string();
 
     // If body consists only of whitespace (e.g. after trimming only newlines), make it empty.
     if body.trim().is_empty() && !body.contains("\n\n") { // Preserve multi-paragraphs that are just spaces
-        Ok(("".to_string(), footers))
+        ("".to_string(), footers.into_iter().rev().collect())
     } else {
-        Ok((body, footers))
+        (body, footers.into_iter().rev().collect())
     }
 }
 
@@ -590,14 +839,14 @@ mod tests {
     fn test_breaking_change_footer_optional_when_config_allows() {
         let message = "feat!: message\n\nBody only, no BREAKING-CHANGE footer.";
         let config = Config {
-            additional_types: None,
             require_breaking_change_footer: Some(false),
+            ..Config::default()
         };
         let result = parse_commit_message(message, config);
         assert!(result.is_ok());
         let commit = result.unwrap();
         assert_eq!(commit.commit_type, "feat");
-        assert!(commit.footers.get("BREAKING-CHANGE").is_none());
+        assert!(!commit.footers.contains_key("BREAKING-CHANGE"));
     }
 
     #[test]
@@ -605,8 +854,8 @@ mod tests {
         // This should still fail, config only affects ! requiring footer, not footer requiring !
         let message = "feat: message\n\nBREAKING-CHANGE: description here";
          let config = Config {
-            additional_types: None,
             require_breaking_change_footer: Some(false),
+            ..Config::default()
         };
         let result = parse_commit_message(message, config);
        assert!(result.is_err());
@@ -647,4 +896,141 @@ mod tests {
         assert_eq!(commit.footers.get("Ticket").unwrap(), "Ref: #123"); // Key "Ticket", Value "Ref: #123"
         assert_eq!(commit.footers.get("Details").unwrap(), "Contains a colon : in value");
     }
+
+    #[test]
+    fn test_additional_type_is_accepted() {
+        let message = "release: cut 2.0";
+        let config = Config {
+            additional_types: Some(vec!["release".to_string()]),
+            ..default_config()
+        };
+        let commit = parse_commit_message(message, config).unwrap();
+        assert_eq!(commit.commit_type, "release");
+        assert_eq!(commit.subject, "cut 2.0");
+    }
+
+    #[test]
+    fn test_type_allowlist_and_header_rules() {
+        let config = Config {
+            types: Some(vec!["feat".to_string(), "fix".to_string()]),
+            max_subject_length: Some(10),
+            ..default_config()
+        };
+        assert_eq!(
+            parse_commit_message("docs: readme", config.clone()).unwrap_err(),
+            "Invalid type: 'docs'. Allowed types: [\"feat\", \"fix\"]"
+        );
+        assert_eq!(
+            parse_commit_message("fix: a rather long subject", config).unwrap_err(),
+            "Subject is 21 characters long, maximum is 10"
+        );
+    }
+
+    #[test]
+    fn test_validate_collects_all_diagnostics() {
+        let message = "feat(parsr)!: \n\nBody only.";
+        let config = Config {
+            scopes: Some(vec!["parser".to_string()]),
+            ..default_config()
+        };
+        let diagnostics = validate_commit_message(message, &config).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid scope: 'parsr'. Allowed scopes: [\"parser\"]",
+                "Subject is empty",
+                "Commit message with '!' in header must include 'BREAKING-CHANGE' or 'BREAKING CHANGE' in footers",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_recovers_after_invalid_header_token() {
+        let message = "feat add: thing\n\nBREAKING CHANGE: removed";
+        let diagnostics = validate_commit_message(message, &default_config()).unwrap_err();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].message.starts_with("Invalid token in header: Text(\"add\")"));
+        assert_eq!(
+            diagnostics[1].message,
+            "Commit message with 'BREAKING-CHANGE' or 'BREAKING CHANGE' in footers must include '!' in the header"
+        );
+    }
+
+    #[test]
+    fn test_parentheses_in_body_do_not_fail_lexing() {
+        let message = "fix: handle input\n\nThe old code (see the report failed here.";
+        let commit = parse_commit_message(message, default_config()).unwrap();
+        assert_eq!(commit.body.as_deref(), Some("The old code (see the report failed here."));
+    }
+
+    #[test]
+    fn test_suggestions_for_misspelled_type_scope_and_footer() {
+        let config = Config {
+            scopes: Some(vec!["parser".to_string(), "cli".to_string()]),
+            ..default_config()
+        };
+        let diagnostics = validate_commit_message("feta: add thing", &config).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].to_string(), "Unknown commit type: 'feta'. Did you mean 'feat'?");
+
+        let message = "fix(parsr): handle input\n\nSigned-of-by: Jane <jane@example.com>";
+        let diagnostics = validate_commit_message(message, &config).unwrap_err();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].suggestion.as_ref().unwrap().unambiguous(), Some("parser"));
+        assert_eq!(diagnostics[1].suggestion.as_ref().unwrap().unambiguous(), Some("Signed-off-by"));
+
+        let fixed = crate::suggest::apply_suggestions(message, diagnostics.iter().filter_map(|d| d.suggestion.as_ref()));
+        assert_eq!(
+            fixed.as_deref(),
+            Some("fix(parser): handle input\n\nSigned-off-by: Jane <jane@example.com>")
+        );
+    }
+
+    #[test]
+    fn test_footer_abbreviation_is_not_flagged() {
+        let message = "fix: handle input\n\nRef: #12";
+        assert!(validate_commit_message(message, &default_config()).is_ok());
+    }
+
+    #[test]
+    fn test_render_round_trips_parsed_messages() {
+        let messages = [
+            "docs: improve documentation",
+            "feat(parser)!: drop legacy syntax\n\nFirst paragraph.\n\nSecond paragraph.\n\nBREAKING CHANGE: old syntax removed\nRefs #42",
+            "feat: stuff\n\nBody line 1.\n\n\nBody line 2.\n\nFooter-One: Val1\n\nAnother-Footer: Val2",
+            "fix: resolve issue\n\nIssue #42\nReviewed-by: Another Dev <another@example.com>\nIssue #43",
+        ];
+        for message in messages {
+            let commit = parse_commit_message(message, default_config()).unwrap();
+            let rendered = commit.render();
+            assert_eq!(rendered, message);
+            assert_eq!(parse_commit_message(&rendered, default_config()).unwrap(), commit);
+        }
+    }
+
+    #[test]
+    fn test_footers_keep_written_order_and_duplicates() {
+        let message = "fix: resolve issue\n\nIssue #42\nReviewed-by: Dev\nIssue #43";
+        let commit = parse_commit_message(message, default_config()).unwrap();
+        let tokens: Vec<&str> = commit.footers.keys().collect();
+        assert_eq!(tokens, vec!["Issue", "Reviewed-by", "Issue"]);
+        // The first of several footers with one token wins
+        assert_eq!(commit.footers.get("Issue").unwrap(), "42");
+        assert!(commit.footers.iter().next().is_some_and(|f| f.separator == FooterSeparator::Hash));
+    }
+
+    #[test]
+    fn test_breaking_change_spellings_share_a_key() {
+        let message = "feat!: new api\n\nBREAKING CHANGE: first\nRefs: #1\nBREAKING-CHANGE: second";
+        let commit = parse_commit_message(message, default_config()).unwrap();
+        let keys: Vec<&str> = commit.footers.keys().collect();
+        assert_eq!(keys, vec!["BREAKING-CHANGE", "Refs", "BREAKING-CHANGE"]);
+        // Unlike other tokens, the last BREAKING CHANGE wins, whichever spelling is asked for
+        assert_eq!(commit.footers.get("BREAKING-CHANGE").unwrap(), "second");
+        assert_eq!(commit.footers.get("BREAKING CHANGE").unwrap(), "second");
+        // The spelling as written is kept for rendering
+        assert_eq!(commit.footers.iter().next().unwrap().token, "BREAKING CHANGE");
+        assert_eq!(commit.render(), message);
+    }
 }
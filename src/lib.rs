@@ -1,6 +1,16 @@
 pub mod cli;
+pub mod git;
 pub mod changelog;
 pub mod tui;
+pub mod commitlint;
+pub mod fix;
+pub mod manifest;
+pub mod packages;
+pub mod release;
+pub mod rules;
+pub mod suggest;
+pub mod version;
+pub mod workspace;
 /// # Commit Message Parser
 ///
 /// This module provides a parser for commit messages following the Conventional Commits specification.
@@ -1,12 +1,15 @@
 use std::{fs, os::unix::fs::PermissionsExt, process::Command};
-use toml;
 
 use clap::Parser;
 use colored::Colorize;
 use convy::{
-    cli::{ChangelogCommands, Commands, Cli},
-    lexer::{default_config, parse_commit_message, Config},
+    changelog::formats::Format,
+    cli::{ChangelogCommands, ChangelogFormat, Cli, Commands, ConfigCommands, PlanFormat, ReleaseBump, VersionCommands},
+    lexer::{default_config, validate_commit_message, Config},
+    packages::PackageConfig,
+    suggest::apply_suggestions,
     tui::run_wizard,
+    version::Bump,
 };
 
 fn main() -> Result<(), String> {
@@ -14,14 +17,30 @@ fn main() -> Result<(), String> {
 
     match cli.commands {
         Commands::Parse(arg) => {
-            let config = load_config();
+            let config = load_config(MissingConfig::Error)?;
 
-            match parse_commit_message(&arg.commit, config) {
+            match validate_commit_message(&arg.commit, &config) {
                 Ok(_) => {
                     println!("{} Commit message is valid!", "✔".green());
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "✘ Error:".red(), e);
+                Err(diagnostics) => {
+                    for diagnostic in &diagnostics {
+                        eprintln!("{} {}", "✘ Error:".red(), diagnostic);
+                    }
+
+                    if arg.fix {
+                        let suggestions = diagnostics.iter().filter_map(|d| d.suggestion.as_ref());
+                        match apply_suggestions(&arg.commit, suggestions) {
+                            Some(fixed) => {
+                                println!("\n{}", "Corrected message:".bold());
+                                println!("{}", fixed);
+                                if validate_commit_message(&fixed, &config).is_err() {
+                                    eprintln!("{} The corrected message still has problems.", "!".yellow());
+                                }
+                            }
+                            None => eprintln!("{} No unambiguous suggestion to apply.", "!".yellow()),
+                        }
+                    }
                     std::process::exit(1);
                 }
             };
@@ -76,7 +95,7 @@ fn main() -> Result<(), String> {
                 let commit_msg = include_str!("commit_msg");
 
                 // Ensure .git/hooks directory exists
-                if !fs::metadata(".git/hooks").is_ok() {
+                if fs::metadata(".git/hooks").is_err() {
                     fs::create_dir_all(".git/hooks")
                         .expect("Error creating .git/hooks directory");
                 }
@@ -94,14 +113,57 @@ fn main() -> Result<(), String> {
             Ok(())
         }
         Commands::Changelog(changelog_args) => {
+             let (config, package) = select_package(load_config(MissingConfig::Default)?, changelog_args.package.as_deref())?;
              match changelog_args.command {
-                ChangelogCommands::Init(_) => convy::changelog::init(),
-                ChangelogCommands::Generate(args) => convy::changelog::generate(args.write, args.all),
-                ChangelogCommands::Release(args) => convy::changelog::release(&args.version),
+                ChangelogCommands::Init(_) => convy::changelog::init(&config),
+                ChangelogCommands::Generate(args) => {
+                    let format = match args.format {
+                        ChangelogFormat::Markdown => Format::Markdown,
+                        ChangelogFormat::Json => Format::Json,
+                        ChangelogFormat::Html => Format::Html,
+                        ChangelogFormat::Asciidoc => Format::AsciiDoc,
+                        ChangelogFormat::Rst => Format::Rst,
+                        ChangelogFormat::Debian => Format::Debian,
+                        ChangelogFormat::Rpm => Format::Rpm,
+                    };
+                    convy::changelog::generate(&config, package.as_ref(), args.write, args.all, args.full, format)
+                }
+                ChangelogCommands::Release(args) => convy::changelog::release(&config, package.as_ref(), args.version.as_deref(), args.prerelease.as_deref()),
+                ChangelogCommands::Lint(args) => convy::changelog::lint::run_lint(&config, !args.no_tags),
             }
         }
+        Commands::Config(config_args) => match config_args.command {
+            ConfigCommands::Import(args) => {
+                convy::commitlint::run_import(args.from.as_deref(), args.force, args.dry_run)
+            }
+        },
+        Commands::Version(version_args) => {
+            let (config, package) = select_package(load_config(MissingConfig::Default)?, version_args.package.as_deref())?;
+            match version_args.command {
+                VersionCommands::Next(args) => convy::version::run_next(&config, package.as_ref(), args.explain, args.prerelease.as_deref()),
+                VersionCommands::Set(args) => convy::manifest::run_set(&config, package.as_ref(), &args.version, args.dry_run),
+            }
+        }
+        Commands::Release(args) => {
+            let (config, package) = select_package(load_config(MissingConfig::Default)?, args.package.as_deref())?;
+            let bump = match args.bump {
+                ReleaseBump::Auto => None,
+                ReleaseBump::Major => Some(Bump::Major),
+                ReleaseBump::Minor => Some(Bump::Minor),
+                ReleaseBump::Patch => Some(Bump::Patch),
+            };
+            let format = match args.format {
+                PlanFormat::Table => convy::release::PlanFormat::Table,
+                PlanFormat::Json => convy::release::PlanFormat::Json,
+            };
+            convy::release::run_release(&config, package.as_ref(), bump, args.prerelease.as_deref(), args.dry_run, format)
+        }
+        Commands::Fix(args) => {
+            let config = load_config(MissingConfig::Default)?;
+            convy::fix::run_fix(&args.file, args.check, &config)
+        }
         Commands::Commit(args) => {
-            let config = load_config_safe().unwrap_or_else(default_config);
+            let config = load_config(MissingConfig::Default)?;
             
             let msg = run_wizard(config).map_err(|e| e.to_string())?;
 
@@ -132,22 +194,27 @@ fn main() -> Result<(), String> {
     }
 }
 
-fn load_config() -> Config {
+// What `load_config` does when there is no .convy.toml
+enum MissingConfig {
+    Error,
+    Default,
+}
+
+// Reads .convy.toml; a malformed file is always an error, a missing one depends on the command
+fn load_config(missing: MissingConfig) -> Result<Config, String> {
     match fs::read_to_string(".convy.toml") {
-        Ok(content) => toml::from_str(&content).expect("Error parsing TOML"),
-        Err(_) => {
-            // Check current dir, if not found, we could look recursively up, 
-            // but for now let's strict check as per original behavior or return default?
-            // Original behavior exit(1).
-            eprintln!("Error: .convy.toml not found. Please run `convy init`.");
-            std::process::exit(1);
-        }
+        Ok(content) => toml::from_str(&content).map_err(|e| format!("Failed to parse .convy.toml: {}", e)),
+        Err(_) => match missing {
+            MissingConfig::Error => Err(".convy.toml not found. Please run `convy init`.".to_string()),
+            MissingConfig::Default => Ok(default_config()),
+        },
     }
 }
 
-fn load_config_safe() -> Option<Config> {
-    match fs::read_to_string(".convy.toml") {
-        Ok(content) => toml::from_str(&content).ok(),
-        Err(_) => None,
+// Narrows the config to one of its [[packages]] when --package is given, returning the package alongside
+fn select_package(config: Config, package: Option<&str>) -> Result<(Config, Option<PackageConfig>), String> {
+    match package {
+        Some(name) => Ok((config.for_package(name)?, Some(config.package(name)?.clone()))),
+        None => Ok((config, None)),
     }
 }
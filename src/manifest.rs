@@ -0,0 +1,365 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use colored::Colorize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item};
+
+use crate::lexer::Config;
+use crate::packages::{package_root, PackageConfig};
+
+/// A user-defined version location: the first capture group (or the group named `version`) of `pattern` in `file`
+/// is replaced with the new version.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct VersionReplacement {
+    pub file: String,
+    pub pattern: String,
+}
+
+// Rewrites the version in a file's content, returning `None` if there was nothing to change
+type Updater = fn(&str, &str) -> Result<Option<String>, String>;
+
+/// A file whose version was (or would be) changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bumped {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// Rewrites every version field convy knows about under `root`, keeping formatting and comments intact.
+///
+/// Covers `Cargo.toml` (`package.version` and `workspace.package.version`, plus `Cargo.lock` for the crates that
+/// changed), `package.json`, `pyproject.toml`, a plain `VERSION` file and the configured `[[version.replace]]`
+/// patterns. Nothing is written when `dry_run` is set.
+pub fn set_version(config: &Config, root: &Path, version: &str, dry_run: bool) -> Result<Vec<PathBuf>, String> {
+    let mut bumped = Vec::new();
+
+    let cargo_toml = root.join("Cargo.toml");
+    if let Some(content) = read_optional(&cargo_toml)? {
+        let crates = changed_crates(root, &content)?;
+        if let Some(updated) = update_cargo_toml(&content, version)? {
+            bumped.push(Bumped { path: cargo_toml, content: updated });
+        }
+        // A package inside a workspace shares the lock file at the repository root
+        let cargo_lock = [root.join("Cargo.lock"), PathBuf::from("Cargo.lock")].into_iter().find(|p| p.is_file());
+        if let Some(cargo_lock) = cargo_lock {
+            let lock = fs::read_to_string(&cargo_lock).map_err(|e| format!("Failed to read {}: {}", cargo_lock.display(), e))?;
+            if let Some(updated) = update_cargo_lock(&lock, &crates, version)? {
+                bumped.push(Bumped { path: cargo_lock, content: updated });
+            }
+        }
+    }
+
+    let updaters: [(&str, Updater); 3] = [
+        ("package.json", update_package_json),
+        ("pyproject.toml", update_pyproject),
+        ("VERSION", update_version_file),
+    ];
+    for (name, update) in updaters {
+        let path = root.join(name);
+        if let Some(content) = read_optional(&path)? {
+            if let Some(updated) = update(&content, version).map_err(|e| format!("{}: {}", name, e))? {
+                bumped.push(Bumped { path, content: updated });
+            }
+        }
+    }
+
+    for replacement in config.version().replace.iter().flatten() {
+        let path = root.join(&replacement.file);
+        let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        // Earlier updates to the same file must not be lost
+        let content = bumped.iter().find(|b| b.path == path).map_or(content, |b| b.content.clone());
+        let updated = update_with_pattern(&content, &replacement.pattern, version)
+            .map_err(|e| format!("{}: {}", replacement.file, e))?;
+        match bumped.iter_mut().find(|b| b.path == path) {
+            Some(existing) => existing.content = updated,
+            None => bumped.push(Bumped { path, content: updated }),
+        }
+    }
+
+    if !dry_run {
+        for file in &bumped {
+            fs::write(&file.path, &file.content).map_err(|e| format!("Failed to write {}: {}", file.path.display(), e))?;
+        }
+    }
+    Ok(bumped.into_iter().map(|b| b.path).collect())
+}
+
+/// Handles `convy version set`.
+pub fn run_set(config: &Config, package: Option<&PackageConfig>, version: &str, dry_run: bool) -> Result<(), String> {
+    let version = crate::changelog::parse_version(version)?.to_string();
+    let files = set_version(config, &package_root(package), &version, dry_run)?;
+    if files.is_empty() {
+        println!("{} No version files found", "!".yellow());
+    }
+    for file in files {
+        let verb = if dry_run { "Would update" } else { "Updated" };
+        println!("{} {} {}", "✔".green(), verb, file.display());
+    }
+    Ok(())
+}
+
+pub(crate) fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+// Replaces a string value, keeping the comments and spacing around it
+pub(crate) fn set_string(item: &mut Item, new: &str) {
+    if let Some(value) = item.as_value_mut().filter(|v| v.is_str()) {
+        let decor = value.decor().clone();
+        *value = new.into();
+        *value.decor_mut() = decor;
+    }
+}
+
+pub(crate) fn parse_toml(content: &str) -> Result<DocumentMut, String> {
+    content.parse::<DocumentMut>().map_err(|e| e.to_string())
+}
+
+// `None` when nothing changed, so untouched files are not reported
+fn changed(original: &str, updated: String) -> Option<String> {
+    (updated != original).then_some(updated)
+}
+
+/// Sets `package.version` and `workspace.package.version`; versions inherited with `version.workspace = true` are
+/// left alone.
+pub fn update_cargo_toml(content: &str, version: &str) -> Result<Option<String>, String> {
+    let mut doc = parse_toml(content)?;
+    if let Some(item) = doc.get_mut("package").and_then(|p| p.get_mut("version")) {
+        set_string(item, version);
+    }
+    if let Some(item) = doc
+        .get_mut("workspace")
+        .and_then(|w| w.get_mut("package"))
+        .and_then(|p| p.get_mut("version"))
+    {
+        set_string(item, version);
+    }
+    Ok(changed(content, doc.to_string()))
+}
+
+// Names of the crates whose version moves with this manifest: the root package, and workspace members that inherit
+// the workspace version
+fn changed_crates(root: &Path, content: &str) -> Result<Vec<String>, String> {
+    let doc = parse_toml(content)?;
+    let mut crates = Vec::new();
+    if let Some(package) = doc.get("package") {
+        if package.get("version").is_some_and(Item::is_str) {
+            crates.extend(package.get("name").and_then(Item::as_str).map(str::to_string));
+        }
+    }
+
+    let Some(workspace) = doc.get("workspace") else {
+        return Ok(crates);
+    };
+    if workspace.get("package").and_then(|p| p.get("version")).is_none() {
+        return Ok(crates);
+    }
+    for dir in member_dirs(root, workspace) {
+        let Some(member) = read_optional(&dir.join("Cargo.toml"))? else {
+            continue;
+        };
+        let member = parse_toml(&member).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let Some(package) = member.get("package") else {
+            continue;
+        };
+        let inherits = package
+            .get("version")
+            .and_then(|v| v.get("workspace"))
+            .and_then(Item::as_bool)
+            .unwrap_or(false);
+        if inherits {
+            crates.extend(package.get("name").and_then(Item::as_str).map(str::to_string));
+        }
+    }
+    Ok(crates)
+}
+
+/// The member directories of a `[workspace]` table: its `members`, leaving out anything under an `exclude` path.
+pub(crate) fn member_dirs(root: &Path, workspace: &Item) -> Vec<PathBuf> {
+    let paths = |key: &str| {
+        let entries = workspace.get(key).and_then(Item::as_array);
+        entries.into_iter().flatten().filter_map(|p| p.as_str()).flat_map(|p| expand_member(root, p)).collect::<Vec<_>>()
+    };
+    let normal = |p: &Path| p.components().filter(|c| *c != Component::CurDir).collect::<PathBuf>();
+    let excluded: Vec<PathBuf> = paths("exclude").iter().map(|p| normal(p)).collect();
+    paths("members").into_iter().filter(|dir| !excluded.iter().any(|e| normal(dir).starts_with(e))).collect()
+}
+
+// Workspace members are paths, optionally ending in a `*` wildcard
+fn expand_member(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let Some(parent) = pattern.strip_suffix('*') else {
+        return vec![root.join(pattern)];
+    };
+    let Ok(entries) = fs::read_dir(root.join(parent)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+    dirs.sort();
+    dirs
+}
+
+/// Updates the entries of local crates (those without a `source`) named in `crates`.
+pub fn update_cargo_lock(content: &str, crates: &[String], version: &str) -> Result<Option<String>, String> {
+    if crates.is_empty() {
+        return Ok(None);
+    }
+    let mut doc = parse_toml(content)?;
+    if let Some(packages) = doc.get_mut("package").and_then(Item::as_array_of_tables_mut) {
+        for package in packages.iter_mut() {
+            let is_local = !package.contains_key("source");
+            let name = package.get("name").and_then(Item::as_str).unwrap_or_default();
+            if is_local && crates.iter().any(|c| c == name) {
+                if let Some(item) = package.get_mut("version") {
+                    set_string(item, version);
+                }
+            }
+        }
+    }
+    Ok(changed(content, doc.to_string()))
+}
+
+/// Sets the top-level `"version"` of a package.json without reformatting the rest of the file.
+pub fn update_package_json(content: &str, version: &str) -> Result<Option<String>, String> {
+    let parsed: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let Some(old) = parsed.get("version").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+
+    // The top-level key is the first one at nesting depth one
+    let re = Regex::new(r#""version"\s*:\s*"([^"]*)""#).unwrap();
+    for caps in re.captures_iter(content) {
+        let whole = caps.get(0).unwrap();
+        let value = caps.get(1).unwrap();
+        if value.as_str() == old && json_depth(&content[..whole.start()]) == 1 {
+            let updated = format!("{}{}{}", &content[..value.start()], version, &content[value.end()..]);
+            return Ok(changed(content, updated));
+        }
+    }
+    Ok(None)
+}
+
+// Object/array nesting depth at the end of `prefix`, ignoring brackets inside strings
+fn json_depth(prefix: &str) -> usize {
+    let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+    for c in prefix.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' | '[' if !in_string => depth += 1,
+            '}' | ']' if !in_string => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
+
+/// Sets `project.version` (PEP 621) or `tool.poetry.version`.
+pub fn update_pyproject(content: &str, version: &str) -> Result<Option<String>, String> {
+    let mut doc = parse_toml(content)?;
+    if let Some(item) = doc.get_mut("project").and_then(|p| p.get_mut("version")) {
+        set_string(item, version);
+    }
+    if let Some(item) = doc
+        .get_mut("tool")
+        .and_then(|t| t.get_mut("poetry"))
+        .and_then(|p| p.get_mut("version"))
+    {
+        set_string(item, version);
+    }
+    Ok(changed(content, doc.to_string()))
+}
+
+/// Replaces the whole content of a plain VERSION file, keeping its trailing newline. A file holding anything besides
+/// the version is refused; such files need a `[[version.replace]]` pattern.
+pub fn update_version_file(content: &str, version: &str) -> Result<Option<String>, String> {
+    if content.split_whitespace().nth(1).is_some() {
+        return Err("holds more than a version; add a [[version.replace]] pattern for it".to_string());
+    }
+    let trailing = &content[content.trim_end().len()..];
+    Ok(changed(content, format!("{}{}", version, trailing)))
+}
+
+/// Replaces the group named `version`, or the first capture group, of every match of `pattern`.
+pub fn update_with_pattern(content: &str, pattern: &str, version: &str) -> Result<String, String> {
+    let re = Regex::new(pattern).map_err(|e| format!("invalid pattern: {}", e))?;
+    if re.captures_len() < 2 {
+        return Err(format!("pattern '{}' has no capture group for the version", pattern));
+    }
+
+    let mut updated = String::new();
+    let mut last = 0;
+    let mut matched = false;
+    for caps in re.captures_iter(content) {
+        let Some(group) = caps.name("version").or_else(|| caps.get(1)) else {
+            continue;
+        };
+        updated.push_str(&content[last..group.start()]);
+        updated.push_str(version);
+        last = group.end();
+        matched = true;
+    }
+    if !matched {
+        return Err(format!("pattern '{}' did not match", pattern));
+    }
+    updated.push_str(&content[last..]);
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_cargo_toml_keeps_formatting() {
+        let content = "[package]\nname = \"demo\"\nversion   = \"0.1.0\" # bumped by convy\n\n[dependencies]\nserde = { version = \"1\" }\n";
+        let updated = update_cargo_toml(content, "0.2.0").unwrap().unwrap();
+        assert_eq!(
+            updated,
+            "[package]\nname = \"demo\"\nversion   = \"0.2.0\" # bumped by convy\n\n[dependencies]\nserde = { version = \"1\" }\n"
+        );
+
+        let inherited = "[package]\nname = \"member\"\nversion.workspace = true\n";
+        assert_eq!(update_cargo_toml(inherited, "0.2.0").unwrap(), None);
+    }
+
+    #[test]
+    fn test_update_cargo_lock_only_touches_local_crates() {
+        let lock = "[[package]]\nname = \"demo\"\nversion = \"0.1.0\"\n\n[[package]]\nname = \"serde\"\nversion = \"0.1.0\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n";
+        let updated = update_cargo_lock(lock, &["demo".to_string(), "serde".to_string()], "0.2.0").unwrap().unwrap();
+        assert_eq!(updated, lock.replacen("0.1.0", "0.2.0", 1));
+    }
+
+    #[test]
+    fn test_update_package_json_top_level_only() {
+        let content = "{\n  \"name\": \"demo\",\n  \"engines\": { \"version\": \"1.0.0\" },\n  \"version\":  \"1.0.0\"\n}\n";
+        let updated = update_package_json(content, "1.1.0").unwrap().unwrap();
+        assert_eq!(updated, content.replace("\"version\":  \"1.0.0\"", "\"version\":  \"1.1.0\""));
+    }
+
+    #[test]
+    fn test_update_pyproject_and_version_file() {
+        let content = "[tool.poetry]\nname = \"demo\"\nversion = \"1.0.0\"\n";
+        assert_eq!(update_pyproject(content, "1.1.0").unwrap().unwrap(), content.replace("1.0.0", "1.1.0"));
+        assert_eq!(update_version_file("1.0.0\n", "1.1.0").unwrap().unwrap(), "1.1.0\n");
+        assert!(update_version_file("1.0.0\nreleased by ci\n", "1.1.0").is_err());
+    }
+
+    #[test]
+    fn test_update_with_pattern() {
+        let content = "pub const VERSION: &str = \"1.0.0\";\n";
+        let updated = update_with_pattern(content, r#"VERSION: &str = "([^"]+)""#, "1.1.0").unwrap();
+        assert_eq!(updated, "pub const VERSION: &str = \"1.1.0\";\n");
+        assert!(update_with_pattern(content, "VERSION", "1.1.0").is_err());
+        assert!(update_with_pattern(content, "NOPE = (.*)", "1.1.0").is_err());
+        // An empty group at the very start is still a match
+        assert_eq!(update_with_pattern("# version\n", r"^(\S*)# version", "1.1.0").unwrap(), "1.1.0# version\n");
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::changelog::Entry;
+use crate::lexer::Config;
+use crate::version::TagPattern;
+
+/// One `[[packages]]` entry: an independently versioned part of a monorepo.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PackageConfig {
+    pub name: String,
+    pub path: String,                  // Directory or glob, e.g. "crates/cli" or "crates/cli/**"
+    pub scopes: Option<Vec<String>>,   // Commit scopes that belong to this package wherever the files are
+    pub tag_pattern: Option<String>,   // Defaults to "{name}@{version}"
+    pub changelog: Option<String>,     // Defaults to CHANGELOG.md inside the package directory
+}
+
+impl PackageConfig {
+    /// The package directory: the part of `path` before the first wildcard.
+    pub fn root(&self) -> PathBuf {
+        let literal: Vec<&str> = self
+            .path
+            .split('/')
+            .take_while(|segment| !segment.contains(['*', '?', '[']))
+            .collect();
+        match literal.join("/") {
+            root if root.is_empty() => PathBuf::from("."),
+            root => PathBuf::from(root),
+        }
+    }
+
+    pub fn tag_pattern(&self) -> Result<TagPattern, String> {
+        let pattern = self.tag_pattern.as_deref().unwrap_or("{name}@{version}");
+        TagPattern::new(&pattern.replace("{name}", &self.name))
+    }
+
+    pub fn changelog_path(&self) -> String {
+        self.changelog
+            .clone()
+            .unwrap_or_else(|| self.root().join("CHANGELOG.md").to_string_lossy().to_string())
+    }
+
+    /// Whether a commit belongs to this package, by its scope or by the files it touched.
+    pub fn owns(&self, entry: &Entry) -> bool {
+        let by_scope = entry
+            .commit
+            .scope
+            .as_ref()
+            .is_some_and(|scope| self.scopes.iter().flatten().any(|s| s == scope));
+        by_scope || entry.files.iter().any(|file| path_matches(&self.path, file))
+    }
+}
+
+/// Matches a repository-relative file against a package path: a plain directory matches everything below it,
+/// otherwise `*` and `?` match within one path segment and `**` matches across segments.
+pub fn path_matches(pattern: &str, file: &str) -> bool {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    if !pattern.contains(['*', '?']) {
+        return pattern.is_empty() || pattern == "." || file == pattern || file.starts_with(&format!("{}/", pattern));
+    }
+
+    let mut re = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                re.push_str(".*");
+            }
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    // A pattern naming directories also covers the files inside them
+    re.push_str("(/.*)?$");
+    Regex::new(&re).is_ok_and(|re| re.is_match(file))
+}
+
+impl Config {
+    /// The configured `[[packages]]`, empty for a single-package repository.
+    pub fn packages(&self) -> &[PackageConfig] {
+        self.packages.as_deref().unwrap_or_default()
+    }
+
+    /// The `[[packages]]` entry with this name.
+    pub fn package(&self, name: &str) -> Result<&PackageConfig, String> {
+        self.packages().iter().find(|p| p.name == name).ok_or_else(|| {
+            let names: Vec<&str> = self.packages().iter().map(|p| p.name.as_str()).collect();
+            if names.is_empty() {
+                format!("Unknown package '{}': no [[packages]] are configured", name)
+            } else {
+                format!("Unknown package '{}'. Configured packages: {}", name, names.join(", "))
+            }
+        })
+    }
+
+    /// A copy of the config narrowed to one package: its changelog and its tags. Which commits the package owns is
+    /// decided by the [`PackageConfig`] passed alongside.
+    pub fn for_package(&self, name: &str) -> Result<Config, String> {
+        let package = self.package(name)?;
+        let mut config = self.clone();
+        let mut changelog = config.changelog();
+        changelog.path = Some(package.changelog_path());
+        config.changelog = Some(changelog);
+        let mut version = config.version();
+        version.tag_pattern = Some(package.tag_pattern.as_deref().unwrap_or("{name}@{version}").replace("{name}", name));
+        config.version = Some(version);
+        Ok(config)
+    }
+}
+
+/// Where the version files of the selected package (or the repository) live.
+pub fn package_root(package: Option<&PackageConfig>) -> PathBuf {
+    package.map_or(PathBuf::from("."), PackageConfig::root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_matches() {
+        assert!(path_matches("crates/cli", "crates/cli/src/main.rs"));
+        assert!(!path_matches("crates/cli", "crates/cli-utils/src/lib.rs"));
+        assert!(path_matches("crates/*/src", "crates/core/src/lib.rs"));
+        assert!(!path_matches("crates/*/src", "crates/core/tests/it.rs"));
+        assert!(path_matches("packages/**/*.ts", "packages/web/src/app.ts"));
+        assert!(path_matches(".", "README.md"));
+    }
+
+    #[test]
+    fn test_package_defaults() {
+        let package = PackageConfig { name: "cli".to_string(), path: "crates/cli/**".to_string(), ..PackageConfig::default() };
+        assert_eq!(package.root(), PathBuf::from("crates/cli"));
+        assert_eq!(package.changelog_path(), "crates/cli/CHANGELOG.md");
+        assert_eq!(package.tag_pattern().unwrap().format(&semver::Version::new(1, 0, 0)), "cli@1.0.0");
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use semver::Version;
+use serde::Serialize;
+
+use crate::changelog;
+use crate::git;
+use crate::lexer::{validate_commit_message, Config};
+use crate::manifest::set_version;
+use crate::packages::{package_root, PackageConfig};
+use crate::version::{bump_version, next_version, Bump};
+use crate::workspace::{self, RequirementChange};
+
+/// How `convy release` prints its plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+    Table,
+    Json,
+}
+
+// One version to cut: for the whole repository, or for a single package of a monorepo
+struct Plan {
+    config: Config,
+    package: Option<PackageConfig>,
+    crate_name: Option<String>,
+    current: Option<Version>,
+    version: Version,
+    tag: String,
+    notes: String,
+    reason: String,
+}
+
+// The plan as printed by `--format json`; field names are part of the output format
+#[derive(Serialize)]
+struct PlanOutput<'a> {
+    releases: Vec<ReleaseOutput<'a>>,
+    requirements: &'a [RequirementChange],
+}
+
+#[derive(Serialize)]
+struct ReleaseOutput<'a> {
+    package: Option<&'a str>,
+    #[serde(rename = "crate")]
+    crate_name: Option<&'a str>,
+    current: Option<String>,
+    version: String,
+    tag: &'a str,
+    reason: &'a str,
+}
+
+/// Cuts a release in one step: works out the version, updates the changelog and version files, then commits and tags.
+///
+/// `bump` forces the size of the bump; `None` derives it from the commits. With `dry_run` nothing is written and the
+/// planned commit and tag are printed instead. When `[[packages]]` are configured and no `package` is selected, every
+/// package with releasable changes is released in one commit with a tag per package. Packages that are crates of a
+/// Cargo workspace also get their requirements on each other updated, and a dependent whose requirement changes is
+/// released with a patch bump.
+pub fn run_release(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    bump: Option<Bump>,
+    prerelease: Option<&str>,
+    dry_run: bool,
+    format: PlanFormat,
+) -> Result<(), String> {
+    let mut plans = if package.is_none() && !config.packages().is_empty() {
+        let mut plans = Vec::new();
+        for package in config.packages() {
+            if let Some(plan) = plan(&config.for_package(&package.name)?, Some(package), bump, prerelease)? {
+                plans.push(plan);
+            }
+        }
+        if plans.is_empty() {
+            return Err("No package has commits that call for a new version. Use --bump to force one.".to_string());
+        }
+        plans
+    } else {
+        match plan(config, package, bump, prerelease)? {
+            Some(plan) => vec![plan],
+            None => {
+                return Err("No commits since the last release call for a new version. Use --bump to force one.".to_string())
+            }
+        }
+    };
+
+    let requirements = propagate(config, package, &mut plans, prerelease)?;
+    let tags: Vec<&str> = plans.iter().map(|p| p.tag.as_str()).collect();
+    let commit_message = format!("chore(release): {}", tags.join(", "));
+    // A commit-msg hook running convy would otherwise reject the commit after the files are rewritten
+    if let Err(diagnostics) = validate_commit_message(&commit_message, config) {
+        let problems: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        return Err(format!(
+            "The release commit \"{}\" does not pass .convy.toml ({}). Allow the chore type and the release scope.",
+            commit_message,
+            problems.join("; ")
+        ));
+    }
+
+    match format {
+        PlanFormat::Table => print_plan(&plans, &requirements),
+        PlanFormat::Json => {
+            let output = PlanOutput {
+                releases: plans
+                    .iter()
+                    .map(|p| ReleaseOutput {
+                        package: p.package.as_ref().map(|p| p.name.as_str()),
+                        crate_name: p.crate_name.as_deref(),
+                        current: p.current.as_ref().map(Version::to_string),
+                        version: p.version.to_string(),
+                        tag: &p.tag,
+                        reason: &p.reason,
+                    })
+                    .collect(),
+                requirements: &requirements,
+            };
+            println!("{}", serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?);
+            if dry_run {
+                return Ok(());
+            }
+        }
+    }
+
+    if dry_run {
+        for plan in &plans {
+            println!(
+                "{} Would release {} as {} (current: {})",
+                "ℹ".blue(),
+                plan.version,
+                plan.tag,
+                describe(plan.current.as_ref())
+            );
+            println!("{} Would update {}", "ℹ".blue(), plan.config.changelog().path());
+            for file in set_version(&plan.config, &package_root(plan.package.as_ref()), &plan.version.to_string(), true)? {
+                println!("{} Would update {}", "ℹ".blue(), file.display());
+            }
+        }
+        for manifest in requirement_manifests(&requirements) {
+            println!("{} Would update {}", "ℹ".blue(), manifest);
+        }
+        println!("{} Would commit: {}", "ℹ".blue(), commit_message);
+        for plan in &plans {
+            println!("{} Would create tag {} with message:\n", "ℹ".blue(), plan.tag);
+            println!("{}", tag_message(plan));
+        }
+        return Ok(());
+    }
+
+    // Only the files convy touches are committed, so anything else staged or modified would be mixed in or lost
+    let status = git::run(&["status", "--porcelain", "--untracked-files=no"])?;
+    if !status.trim().is_empty() {
+        return Err("Working tree has uncommitted changes. Commit or stash them before releasing.".to_string());
+    }
+
+    // Everything the release may write, so a failure part way through can put it back
+    let originals = read_originals(&planned_files(&plans, &requirements)?)?;
+    let mut committed = false;
+    let mut tagged = Vec::new();
+    if let Err(e) = publish(&plans, &requirements, &commit_message, format, &mut committed, &mut tagged) {
+        return Err(match roll_back(&originals, committed, &tagged) {
+            Ok(()) => format!("{}. The release was rolled back.", e),
+            Err(rollback) => format!(
+                "{}. Rolling it back failed as well ({}); check `git status`, `git log` and `git tag` before retrying.",
+                e, rollback
+            ),
+        });
+    }
+
+    progress(format, format!("{} Committed \"{}\" and tagged {}", "✔".green(), commit_message, tags.join(", ")));
+    progress(format, "Push with `git push --follow-tags` to publish the release.".to_string());
+    Ok(())
+}
+
+// Writes the files, commits and tags, recording how far it got in `committed` and `tagged`
+fn publish(
+    plans: &[Plan],
+    requirements: &[RequirementChange],
+    commit_message: &str,
+    format: PlanFormat,
+    committed: &mut bool,
+    tagged: &mut Vec<String>,
+) -> Result<(), String> {
+    let mut files = Vec::new();
+    for plan in plans {
+        files.extend(apply(plan, format)?);
+    }
+    if !requirements.is_empty() {
+        let root = Path::new(".");
+        workspace::requirement_changes(root, &workspace::members(root)?, &crate_versions(plans), true)?;
+        for manifest in requirement_manifests(requirements) {
+            if !files.contains(&manifest) {
+                progress(format, format!("{} Updated {}", "✔".green(), manifest));
+                files.push(manifest);
+            }
+        }
+    }
+    // An ignored Cargo.lock is updated on disk but cannot be committed
+    files.retain(|f| !git::is_ignored(f));
+    files.sort();
+    files.dedup();
+
+    let mut add = vec!["add", "--"];
+    add.extend(files.iter().map(String::as_str));
+    git::run(&add)?;
+    git::run(&["commit", "-q", "-m", commit_message])?;
+    *committed = true;
+    for plan in plans {
+        // Verbatim, so the `###` headings of the notes are not stripped as comments
+        git::run(&["tag", "-a", &plan.tag, "--cleanup=verbatim", "-m", &tag_message(plan)])?;
+        tagged.push(plan.tag.clone());
+    }
+    Ok(())
+}
+
+// The changelogs, version files and manifests the plans will write
+fn planned_files(plans: &[Plan], requirements: &[RequirementChange]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for plan in plans {
+        files.push(PathBuf::from(plan.config.changelog().path()));
+        files.extend(set_version(&plan.config, &package_root(plan.package.as_ref()), &plan.version.to_string(), true)?);
+    }
+    files.extend(requirement_manifests(requirements).into_iter().map(PathBuf::from));
+    Ok(files)
+}
+
+// Each file the release writes with its content beforehand, `None` for files it creates
+type Originals = Vec<(PathBuf, Option<Vec<u8>>)>;
+
+fn read_originals(files: &[PathBuf]) -> Result<Originals, String> {
+    files
+        .iter()
+        .map(|file| match file.is_file() {
+            true => fs::read(file).map(|content| (file.clone(), Some(content))),
+            false => Ok((file.clone(), None)),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())
+}
+
+// Undoes a release that failed part way: drops its tags and commit, and puts the files back as they were
+fn roll_back(originals: &Originals, committed: bool, tagged: &[String]) -> Result<(), String> {
+    for tag in tagged {
+        git::run(&["tag", "-d", tag])?;
+    }
+    if committed {
+        git::run(&["reset", "-q", "--soft", "HEAD~1"])?;
+    }
+    let mut reset = vec!["reset", "-q", "--"];
+    reset.extend(originals.iter().filter_map(|(file, _)| file.to_str()));
+    git::run(&reset)?;
+    for (file, content) in originals {
+        match content {
+            Some(content) => fs::write(file, content),
+            None if file.exists() => fs::remove_file(file),
+            None => Ok(()),
+        }
+        .map_err(|e| format!("Failed to restore {}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+// Works out the next version; `None` when the commits call for no release and no bump was forced
+fn plan(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    bump: Option<Bump>,
+    prerelease: Option<&str>,
+) -> Result<Option<Plan>, String> {
+    let next = next_version(config, package, prerelease)?;
+    let (version, reason) = match bump {
+        Some(bump) => (
+            bump_version(next.current.as_ref(), next.last_final.as_ref(), bump, prerelease)?,
+            format!("{} bump requested", bump.name()),
+        ),
+        None if next.bump == Bump::None => return Ok(None),
+        None => (next.next, format!("{} bump from commits", next.bump.name())),
+    };
+
+    Ok(Some(Plan {
+        tag: config.tag_pattern()?.format(&version),
+        notes: changelog::release_notes(config, package, &version)?,
+        crate_name: None,
+        current: next.current,
+        version,
+        config: config.clone(),
+        package: package.cloned(),
+        reason,
+    }))
+}
+
+// Carries the plans through the Cargo workspace: finds the requirements on released crates that must change, adds a
+// patch release for each package whose published manifest changes as a result, and puts the plans in publish order.
+// Without a workspace, or without `[[packages]]`, the plans are left as they are. With a `selected` package, dependents
+// are only reported.
+fn propagate(
+    config: &Config,
+    selected: Option<&PackageConfig>,
+    plans: &mut Vec<Plan>,
+    prerelease: Option<&str>,
+) -> Result<Vec<RequirementChange>, String> {
+    let root = Path::new(".");
+    if config.packages().is_empty() || !root.join("Cargo.toml").is_file() {
+        return Ok(Vec::new());
+    }
+    let members = workspace::members(root)?;
+    let crate_of = |name: &str| {
+        let package = config.packages().iter().find(|p| p.name == name)?;
+        members.iter().find(|m| workspace::same_dir(&m.dir, &package.root())).map(|m| m.name.clone())
+    };
+    for plan in plans.iter_mut() {
+        plan.crate_name = plan.package.as_ref().and_then(|p| crate_of(&p.name));
+    }
+
+    loop {
+        let changes = workspace::requirement_changes(root, &members, &crate_versions(plans), false)?;
+        let affected = workspace::affected_crates(&changes, &members);
+        let mut added = false;
+        for package in config.packages() {
+            let Some(crate_name) = crate_of(&package.name).filter(|c| affected.contains(c)) else {
+                continue;
+            };
+            if plans.iter().any(|p| p.crate_name.as_deref() == Some(crate_name.as_str())) {
+                continue;
+            }
+            if selected.is_some() {
+                // Only the selected package is released; its dependents are left to a later release
+                eprintln!("{} {} depends on a released crate; release it as well", "!".yellow(), package.name);
+                continue;
+            }
+            let dependencies: Vec<&str> = changes
+                .iter()
+                .filter(|c| c.dependent.as_deref().is_none_or(|d| d == crate_name))
+                .map(|c| c.dependency.as_str())
+                .collect();
+            let mut plan = plan(&config.for_package(&package.name)?, Some(package), Some(Bump::Patch), prerelease)?
+                .ok_or_else(|| format!("No version could be planned for {}", package.name))?;
+            plan.reason = format!("requirement on {} updated", dependencies.join(", "));
+            plan.crate_name = Some(crate_name);
+            plans.push(plan);
+            added = true;
+        }
+        if !added {
+            let order = workspace::publish_order(&members);
+            plans.sort_by_key(|p| p.crate_name.as_ref().and_then(|c| order.iter().position(|o| o == c)));
+            return Ok(changes);
+        }
+    }
+}
+
+fn crate_versions(plans: &[Plan]) -> BTreeMap<String, Version> {
+    plans.iter().filter_map(|p| Some((p.crate_name.clone()?, p.version.clone()))).collect()
+}
+
+fn requirement_manifests(requirements: &[RequirementChange]) -> Vec<String> {
+    let mut manifests: Vec<String> = requirements
+        .iter()
+        .map(|r| r.manifest.to_string_lossy().trim_start_matches("./").to_string())
+        .collect();
+    manifests.sort();
+    manifests.dedup();
+    manifests
+}
+
+fn print_plan(plans: &[Plan], requirements: &[RequirementChange]) {
+    let rows: Vec<[String; 5]> = plans
+        .iter()
+        .map(|p| {
+            [
+                p.package.as_ref().map_or("-".to_string(), |p| p.name.clone()),
+                describe(p.current.as_ref()),
+                p.version.to_string(),
+                p.tag.clone(),
+                p.reason.clone(),
+            ]
+        })
+        .collect();
+    let header = ["Package", "Current", "Next", "Tag", "Reason"].map(String::from);
+    let widths: Vec<usize> = (0..5)
+        .map(|i| rows.iter().chain([&header]).map(|r| r[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    let line = |row: &[String; 5]| {
+        let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        cells.join("  ").trim_end().to_string()
+    };
+
+    println!("{}", line(&header).bold());
+    for row in &rows {
+        println!("{}", line(row));
+    }
+    if !requirements.is_empty() {
+        println!("\n{}", "Requirement updates:".bold());
+        for change in requirements {
+            println!(
+                "  {}: {} \"{}\" -> \"{}\" ({})",
+                change.manifest.to_string_lossy().trim_start_matches("./"),
+                change.dependency,
+                change.from,
+                change.to,
+                change.kind
+            );
+        }
+    }
+    println!();
+}
+
+// Updates the changelog and version files of one plan, returning the repository-relative paths it changed
+fn apply(plan: &Plan, format: PlanFormat) -> Result<Vec<String>, String> {
+    let config = &plan.config;
+    let changelog_config = config.changelog();
+    let changelog_file = changelog_config.path();
+    let folded = changelog::write_release(config, plan.package.as_ref(), &plan.version)?;
+    if !folded.is_empty() {
+        progress(format, format!("{} Folded {} into {}", "ℹ".blue(), folded.join(", "), plan.version));
+    }
+    progress(format, format!("{} Updated {}", "✔".green(), changelog_file));
+
+    let mut files = vec![changelog_file.to_string()];
+    for file in set_version(config, &package_root(plan.package.as_ref()), &plan.version.to_string(), false)? {
+        progress(format, format!("{} Updated {}", "✔".green(), file.display()));
+        files.push(file.to_string_lossy().trim_start_matches("./").to_string());
+    }
+    Ok(files)
+}
+
+// Reports a step of the release; with `--format json` stdout carries only the plan
+fn progress(format: PlanFormat, message: String) {
+    match format {
+        PlanFormat::Table => println!("{}", message),
+        PlanFormat::Json => eprintln!("{}", message),
+    }
+}
+
+fn tag_message(plan: &Plan) -> String {
+    format!("{}\n\n{}", plan.tag, plan.notes.trim_end())
+}
+
+fn describe(version: Option<&semver::Version>) -> String {
+    version.map_or("none".to_string(), |v| v.to_string())
+}
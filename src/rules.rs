@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::Config;
+
+/// Letter-case styles understood by the `*_case` config rules.
+///
+/// The names mirror the ones used by commitlint so imported configs read the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Case {
+    LowerCase,
+    UpperCase,
+    CamelCase,
+    KebabCase,
+    PascalCase,
+    SentenceCase,
+    SnakeCase,
+    StartCase,
+}
+
+impl Case {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Case::LowerCase => "lower-case",
+            Case::UpperCase => "upper-case",
+            Case::CamelCase => "camel-case",
+            Case::KebabCase => "kebab-case",
+            Case::PascalCase => "pascal-case",
+            Case::SentenceCase => "sentence-case",
+            Case::SnakeCase => "snake-case",
+            Case::StartCase => "start-case",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Case> {
+        match name {
+            "lower-case" | "lowercase" => Some(Case::LowerCase),
+            "upper-case" | "uppercase" => Some(Case::UpperCase),
+            "camel-case" => Some(Case::CamelCase),
+            "kebab-case" => Some(Case::KebabCase),
+            "pascal-case" => Some(Case::PascalCase),
+            "sentence-case" | "sentencecase" => Some(Case::SentenceCase),
+            "snake-case" => Some(Case::SnakeCase),
+            "start-case" => Some(Case::StartCase),
+            _ => None,
+        }
+    }
+
+    /// Returns true if `text` is written in this case.
+    pub fn matches(&self, text: &str) -> bool {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match self {
+            Case::LowerCase => text == text.to_lowercase(),
+            Case::UpperCase => text == text.to_uppercase(),
+            Case::CamelCase => {
+                words.len() == 1
+                    && !text.contains(['-', '_'])
+                    && text.chars().next().is_some_and(|c| !c.is_uppercase())
+            }
+            Case::PascalCase => {
+                words.len() == 1
+                    && !text.contains(['-', '_'])
+                    && text.chars().next().is_some_and(|c| c.is_uppercase())
+            }
+            Case::KebabCase => words.len() == 1 && text == text.to_lowercase() && !text.contains('_'),
+            Case::SnakeCase => words.len() == 1 && text == text.to_lowercase() && !text.contains('-'),
+            Case::SentenceCase => {
+                let mut chars = text.chars();
+                match chars.next() {
+                    Some(first) => {
+                        let rest: String = chars.collect();
+                        !first.is_lowercase() && rest == rest.to_lowercase()
+                    }
+                    None => true,
+                }
+            }
+            Case::StartCase => words
+                .iter()
+                .all(|w| w.chars().next().is_some_and(|c| !c.is_lowercase())),
+        }
+    }
+}
+
+/// A case requirement for one part of the header.
+///
+/// `always` lists the cases that are accepted (any one of them), `never` lists the cases that are rejected.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CaseRule {
+    pub always: Option<Vec<Case>>,
+    pub never: Option<Vec<Case>>,
+}
+
+impl CaseRule {
+    /// Checks `text` against the rule, returning a description of the violation if there is one.
+    pub fn check(&self, field: &str, text: &str) -> Result<(), String> {
+        if let Some(always) = &self.always {
+            if !always.is_empty() && !always.iter().any(|c| c.matches(text)) {
+                return Err(format!(
+                    "{} '{}' must be {}",
+                    field,
+                    text,
+                    case_list(always)
+                ));
+            }
+        }
+        if let Some(never) = &self.never {
+            if let Some(c) = never.iter().find(|c| c.matches(text)) {
+                return Err(format!("{} '{}' must not be {}", field, text, c.name()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn case_list(cases: &[Case]) -> String {
+    cases.iter().map(|c| c.name()).collect::<Vec<_>>().join(" or ")
+}
+
+/// Applies the length and case rules from the config to a parsed header, returning every violation.
+pub fn check_header_rules(
+    config: &Config,
+    header: &str,
+    commit_type: &str,
+    scope: Option<&str>,
+    subject: &str,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    if let Some(max) = config.max_header_length {
+        let len = header.chars().count();
+        if len > max {
+            violations.push(format!("Header is {} characters long, maximum is {}", len, max));
+        }
+    }
+    if let Some(max) = config.max_subject_length {
+        let len = subject.chars().count();
+        if len > max {
+            violations.push(format!("Subject is {} characters long, maximum is {}", len, max));
+        }
+    }
+    if let Some(rule) = &config.type_case {
+        violations.extend(rule.check("Type", commit_type).err());
+    }
+    if let (Some(rule), Some(scope)) = (&config.scope_case, scope) {
+        violations.extend(rule.check("Scope", scope).err());
+    }
+    if let Some(rule) = &config.subject_case {
+        violations.extend(rule.check("Subject", subject).err());
+    }
+    violations
+}
+
+/// Applies the body line-length rule from the config, returning every violation.
+pub fn check_body_rules(config: &Config, body: &str) -> Vec<String> {
+    let Some(max) = config.max_body_line_length else {
+        return Vec::new();
+    };
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| line.chars().count() > max)
+        .map(|(i, line)| {
+            format!(
+                "Body line {} is {} characters long, maximum is {}",
+                i + 1,
+                line.chars().count(),
+                max
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_matching() {
+        assert!(Case::LowerCase.matches("add new parser"));
+        assert!(!Case::LowerCase.matches("Add new parser"));
+        assert!(Case::SentenceCase.matches("Add new parser"));
+        assert!(!Case::SentenceCase.matches("Add New Parser"));
+        assert!(Case::StartCase.matches("Add New Parser"));
+        assert!(Case::KebabCase.matches("my-scope"));
+        assert!(Case::SnakeCase.matches("my_scope"));
+        assert!(Case::PascalCase.matches("MyScope"));
+        assert!(Case::CamelCase.matches("myScope"));
+        assert!(Case::UpperCase.matches("API"));
+    }
+
+    #[test]
+    fn test_case_rule_never() {
+        let rule = CaseRule {
+            always: None,
+            never: Some(vec![Case::SentenceCase, Case::UpperCase]),
+        };
+        assert!(rule.check("Subject", "add parser").is_ok());
+        assert_eq!(
+            rule.check("Subject", "Add parser").unwrap_err(),
+            "Subject 'Add parser' must not be sentence-case"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Footer tokens that are common enough to be worth correcting when misspelled.
+pub const WELL_KNOWN_FOOTERS: &[&str] = &["BREAKING CHANGE", "Signed-off-by", "Co-authored-by", "Refs"];
+
+/// A proposed replacement for a span of the original commit message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub span: Range<usize>,
+    pub candidates: Vec<String>,
+}
+
+impl Suggestion {
+    /// The replacement text, if exactly one candidate was found.
+    pub fn unambiguous(&self) -> Option<&str> {
+        match self.candidates.as_slice() {
+            [only] => Some(only),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quoted: Vec<String> = self.candidates.iter().map(|c| format!("'{}'", c)).collect();
+        write!(f, "Did you mean {}?", quoted.join(" or "))
+    }
+}
+
+/// Optimal string alignment distance: Levenshtein plus adjacent transpositions, so `feta` is one edit from `feat`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// Returns the candidates closest to `word`, compared case-insensitively.
+///
+/// Only candidates within a small distance are considered; all candidates tied at the best distance are returned.
+pub fn closest<'a, I>(word: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let word_lower = word.to_lowercase();
+    let max_distance = if word.chars().count() <= 4 { 1 } else { 2 };
+
+    let mut best = Vec::new();
+    let mut best_distance = usize::MAX;
+    for candidate in candidates {
+        let distance = edit_distance(&word_lower, &candidate.to_lowercase());
+        if distance > max_distance || distance > best_distance {
+            continue;
+        }
+        if distance < best_distance {
+            best_distance = distance;
+            best.clear();
+        }
+        if !best.iter().any(|b: &String| b == candidate) {
+            best.push(candidate.to_string());
+        }
+    }
+    best
+}
+
+/// Builds a suggestion for the text at `span`, if any candidate is close enough.
+pub fn suggest<'a, I>(word: &str, span: Range<usize>, candidates: I) -> Option<Suggestion>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let candidates = closest(word, candidates);
+    if candidates.is_empty() || candidates.iter().any(|c| c == word) {
+        None
+    } else {
+        Some(Suggestion { span, candidates })
+    }
+}
+
+/// Applies every unambiguous suggestion to `input`, returning `None` if there was nothing to apply.
+pub fn apply_suggestions<'a, I>(input: &str, suggestions: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a Suggestion>,
+{
+    let mut edits: Vec<(&Range<usize>, &str)> = suggestions
+        .into_iter()
+        .filter_map(|s| s.unambiguous().map(|r| (&s.span, r)))
+        .collect();
+    if edits.is_empty() {
+        return None;
+    }
+
+    // Apply from the end so earlier spans stay valid
+    edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+    let mut fixed = input.to_string();
+    for (span, replacement) in edits {
+        fixed.replace_range(span.clone(), replacement);
+    }
+    Some(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance_counts_transpositions_once() {
+        assert_eq!(edit_distance("feta", "feat"), 1);
+        assert_eq!(edit_distance("parsr", "parser"), 1);
+        assert_eq!(edit_distance("fix", "fix"), 0);
+    }
+
+    #[test]
+    fn test_closest_reports_ties() {
+        assert_eq!(closest("feta", ["feat", "fix", "docs"]), vec!["feat"]);
+        assert_eq!(closest("fux", ["fix", "fax", "docs"]), vec!["fix", "fax"]);
+        assert!(closest("banana", ["feat", "fix"]).is_empty());
+    }
+}
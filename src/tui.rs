@@ -1,4 +1,4 @@
-use crate::lexer::Config;
+use crate::lexer::{CommitMessage, Config, Footer, Footers};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -50,12 +50,15 @@ struct App {
 
 impl App {
     fn new(config: Config) -> App {
-        let mut types = vec![
-            "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert"
-        ]
-        .into_iter()
-        .map(|s| s.to_string())
-        .collect::<Vec<_>>();
+        let mut types = match &config.types {
+            Some(allowed) => allowed.clone(),
+            None => vec![
+                "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert"
+            ]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>(),
+        };
         
         if let Some(extra) = &config.additional_types {
             for t in extra {
@@ -167,18 +170,7 @@ impl App {
             if self.scope_input.trim().is_empty() { None } else { Some(self.scope_input.trim().to_string()) }
         };
 
-        let mut msg = String::new();
-        msg.push_str(commit_type);
-        if let Some(s) = scope {
-            msg.push('(');
-            msg.push_str(&s);
-            msg.push(')');
-        }
-        if self.is_breaking {
-            msg.push('!');
-        }
-        msg.push_str(": ");
-
+        let mut subject = String::new();
         if self.config.emoji.unwrap_or(false) {
             let emoji = match commit_type.as_str() {
                 "feat" => "✨ ",
@@ -194,38 +186,29 @@ impl App {
                 "revert" => "⏪ ",
                 _ => "",
             };
-            msg.push_str(emoji);
+            subject.push_str(emoji);
         }
+        subject.push_str(self.description_input.trim());
 
-        msg.push_str(&self.description_input);
-
-        if !self.body_input.trim().is_empty() {
-            msg.push_str("\n\n");
-            msg.push_str(&self.body_input);
-        }
+        let body = if self.body_input.trim().is_empty() { None } else { Some(self.body_input.clone()) };
 
+        let mut footers = Footers::new();
         if self.is_breaking && !self.breaking_footer_input.trim().is_empty() {
-            msg.push_str("\n\nBREAKING CHANGE: ");
-            msg.push_str(&self.breaking_footer_input);
+            footers.push(Footer::new("BREAKING CHANGE", self.breaking_footer_input.trim()));
         }
-
-        // Add footers
-        if !self.footers.is_empty() {
-             if self.body_input.trim().is_empty() && (!self.is_breaking || self.breaking_footer_input.trim().is_empty()) {
-                msg.push_str("\n\n");
-            } else if !msg.ends_with('\n') {
-                msg.push('\n');
-            }
-
-            for (k, v) in &self.footers {
-                msg.push_str(&format!("{}: {}\n", k, v));
-            }
-            if msg.ends_with('\n') {
-                msg.pop();
-            }
+        for (k, v) in &self.footers {
+            footers.push(Footer::new(k.trim(), v.trim()));
         }
 
-        msg
+        CommitMessage {
+            commit_type: commit_type.clone(),
+            scope,
+            breaking: self.is_breaking,
+            subject,
+            body,
+            footers,
+        }
+        .render()
     }
 }
 
@@ -259,7 +242,7 @@ pub fn run_wizard(config: Config) -> Result<Option<String>, io::Error> {
 
 fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<bool> {
     loop {
-        terminal.draw(|f| ui(f, app)).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        terminal.draw(|f| ui(f, app)).map_err(|e| io::Error::other(e.to_string()))?;
 
         if let Event::Key(key) = event::read()? {
             match app.step {
@@ -367,10 +350,10 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                             app.description_input.pop();
                         }
                     }
-                    KeyCode::Enter => {
-                        if !app.description_input.trim().is_empty() && app.description_input.len() <= 100 {
-                            app.next_step();
-                        }
+                    KeyCode::Enter
+                        if !app.description_input.trim().is_empty() && app.description_input.len() <= 100 =>
+                    {
+                        app.next_step();
                     }
                     KeyCode::Esc => return Ok(false),
                     _ => {}
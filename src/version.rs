@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use colored::Colorize;
+use semver::{Prerelease, Version};
+use serde::{Deserialize, Serialize};
+
+use crate::changelog::{read_commits, version_tags, Entry};
+use crate::lexer::Config;
+use crate::packages::PackageConfig;
+use crate::manifest::VersionReplacement;
+
+/// How far a change moves the version. Ordered, so the biggest bump of a release wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+/// The `[version]` table of `.convy.toml`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct VersionConfig {
+    pub bumps: Option<BTreeMap<String, Bump>>, // Per-type bumps, e.g. { docs = "patch" }; merged over the defaults
+    pub replace: Option<Vec<VersionReplacement>>, // Extra files holding the version
+    pub tag_pattern: Option<String>,               // e.g. "v{version}" or "convy-cli@{version}"
+}
+
+impl VersionConfig {
+    /// The bump a single commit calls for. Breaking changes are always major.
+    pub fn bump_for(&self, entry: &Entry) -> Bump {
+        let commit = &entry.commit;
+        if commit.breaking || commit.footers.contains_key("BREAKING-CHANGE") {
+            return Bump::Major;
+        }
+        if let Some(bump) = self.bumps.as_ref().and_then(|b| b.get(&commit.commit_type)) {
+            return *bump;
+        }
+        match commit.commit_type.as_str() {
+            "feat" => Bump::Minor,
+            "fix" | "perf" => Bump::Patch,
+            _ => Bump::None,
+        }
+    }
+}
+
+impl Config {
+    /// The `[version]` settings, falling back to defaults when the table is absent.
+    pub fn version(&self) -> VersionConfig {
+        self.version.clone().unwrap_or_default()
+    }
+
+    /// How release tags are named.
+    pub fn tag_pattern(&self) -> Result<TagPattern, String> {
+        match self.version.as_ref().and_then(|v| v.tag_pattern.as_deref()) {
+            Some(pattern) => TagPattern::new(pattern),
+            None => Ok(TagPattern::default()),
+        }
+    }
+}
+
+/// Maps versions to tag names and back, e.g. `v{version}` or `convy-cli@{version}`.
+///
+/// Without a configured pattern, tags are created as `v1.2.3` and both `v1.2.3` and `1.2.3` are recognised.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagPattern {
+    prefix: String,
+    suffix: String,
+    // Accept the bare version as well, for the default pattern only
+    lenient: bool,
+}
+
+impl Default for TagPattern {
+    fn default() -> Self {
+        TagPattern { prefix: "v".to_string(), suffix: String::new(), lenient: true }
+    }
+}
+
+impl TagPattern {
+    pub fn new(pattern: &str) -> Result<TagPattern, String> {
+        match pattern.split_once("{version}") {
+            Some((prefix, suffix)) if !suffix.contains("{version}") => {
+                Ok(TagPattern { prefix: prefix.to_string(), suffix: suffix.to_string(), lenient: false })
+            }
+            _ => Err(format!("tag_pattern '{}' must contain {{version}} exactly once", pattern)),
+        }
+    }
+
+    /// The tag name for a version.
+    pub fn format(&self, version: &Version) -> String {
+        format!("{}{}{}", self.prefix, version, self.suffix)
+    }
+
+    /// The version named by a tag, or `None` if the tag does not follow the pattern.
+    pub fn parse(&self, tag: &str) -> Option<Version> {
+        let version = tag
+            .strip_prefix(self.prefix.as_str())
+            .or(if self.lenient { Some(tag) } else { None })?
+            .strip_suffix(self.suffix.as_str())?;
+        Version::parse(version).ok()
+    }
+}
+
+/// Applies a bump to a version, following the 0.x convention that breaking changes only bump the minor version.
+pub fn apply_bump(version: &Version, bump: Bump) -> Version {
+    let bump = if version.major == 0 && bump == Bump::Major { Bump::Minor } else { bump };
+    match bump {
+        Bump::None => version.clone(),
+        Bump::Patch => Version::new(version.major, version.minor, version.patch + 1),
+        Bump::Minor => Version::new(version.major, version.minor + 1, 0),
+        Bump::Major => Version::new(version.major + 1, 0, 0),
+    }
+}
+
+/// Picks the version after `latest` for the given bump, optionally as a pre-release such as `rc`.
+///
+/// `last_final` is the newest release without a pre-release suffix; the bump is measured from it, so a run of
+/// release candidates keeps its target version (`1.4.0-rc.1`, `1.4.0-rc.2`, then `1.4.0`) unless a bigger bump
+/// comes along.
+pub fn bump_version(
+    latest: Option<&Version>,
+    last_final: Option<&Version>,
+    bump: Bump,
+    prerelease: Option<&str>,
+) -> Result<Version, String> {
+    let zero = Version::new(0, 0, 0);
+    let last_final = last_final.unwrap_or(&zero);
+    let pending = latest.filter(|v| !v.pre.is_empty()).map(release_part);
+
+    let mut base = apply_bump(last_final, bump);
+    if let Some(pending) = &pending {
+        if *pending > base {
+            base = pending.clone();
+        }
+    }
+
+    let Some(id) = prerelease else {
+        return Ok(base);
+    };
+    // Continue the numbering of a pre-release with the same identifier and target
+    let number = latest
+        .filter(|v| release_part(v) == base)
+        .and_then(|v| prerelease_number(&v.pre, id))
+        .map_or(1, |n| n + 1);
+    let pre = Prerelease::new(&format!("{}.{}", id, number))
+        .map_err(|e| format!("Invalid pre-release identifier '{}': {}", id, e))?;
+    Ok(Version { pre, ..base })
+}
+
+// The version without pre-release or build metadata
+fn release_part(version: &Version) -> Version {
+    Version::new(version.major, version.minor, version.patch)
+}
+
+// `rc.3` -> 3 for id `rc`
+fn prerelease_number(pre: &Prerelease, id: &str) -> Option<u64> {
+    let (name, number) = pre.as_str().split_once('.')?;
+    if name != id {
+        return None;
+    }
+    number.parse().ok()
+}
+
+/// The outcome of a next-version calculation.
+#[derive(Debug)]
+pub struct NextVersion {
+    /// The latest released version (possibly a pre-release), if there is a version tag.
+    pub current: Option<Version>,
+    /// The latest version tag without a pre-release suffix.
+    pub last_final: Option<Version>,
+    pub next: Version,
+    pub bump: Bump,
+    /// Every commit since the last final release that moves the version, with the bump it calls for.
+    pub reasons: Vec<(Bump, Entry)>,
+}
+
+/// Works out the next version from the commits since the latest final version tag.
+pub fn next_version(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    prerelease: Option<&str>,
+) -> Result<NextVersion, String> {
+    let tags = version_tags(&config.tag_pattern()?)?;
+    let last_final = tags.iter().rev().find(|(v, _)| v.pre.is_empty());
+    let range = match last_final {
+        Some((_, tag)) => format!("{}..HEAD", tag.name),
+        None => "HEAD".to_string(),
+    };
+
+    let version_config = config.version();
+    let reasons: Vec<(Bump, Entry)> = read_commits(&range, config, package)?
+        .into_iter()
+        .map(|entry| (version_config.bump_for(&entry), entry))
+        .filter(|(bump, _)| *bump != Bump::None)
+        .collect();
+    let bump = reasons.iter().map(|(b, _)| *b).max().unwrap_or(Bump::None);
+
+    let current = tags.last().map(|(version, _)| version.clone());
+    let last_final = last_final.map(|(version, _)| version.clone());
+    let next = if bump == Bump::None {
+        current.clone().unwrap_or(Version::new(0, 0, 0))
+    } else {
+        bump_version(current.as_ref(), last_final.as_ref(), bump, prerelease)?
+    };
+    Ok(NextVersion { current, last_final, next, bump, reasons })
+}
+
+/// Prints the next version, and with `explain` the commits that decided it.
+pub fn run_next(
+    config: &Config,
+    package: Option<&PackageConfig>,
+    explain: bool,
+    prerelease: Option<&str>,
+) -> Result<(), String> {
+    let result = next_version(config, package, prerelease)?;
+
+    if explain {
+        match &result.current {
+            Some(current) => eprintln!("{} Current version: {}", "ℹ".blue(), current),
+            None => eprintln!("{} No version tag found, starting from 0.0.0", "ℹ".blue()),
+        }
+        for (bump, entry) in &result.reasons {
+            eprintln!("  {:<5} {} {}", bump.name(), entry.short_hash().dimmed(), entry.commit.header());
+        }
+        eprintln!("{} Bump: {}", "ℹ".blue(), result.bump.name());
+    }
+    if result.bump == Bump::None {
+        eprintln!("{} No commits since the last release call for a new version", "!".yellow());
+    }
+
+    println!("{}", result.next);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_for_commit_types() {
+        let config = VersionConfig {
+            bumps: Some(BTreeMap::from([("docs".to_string(), Bump::Patch), ("perf".to_string(), Bump::None)])),
+            ..VersionConfig::default()
+        };
+        assert_eq!(config.bump_for(&Entry::for_test("feat: a")), Bump::Minor);
+        assert_eq!(config.bump_for(&Entry::for_test("fix: a")), Bump::Patch);
+        assert_eq!(config.bump_for(&Entry::for_test("docs: a")), Bump::Patch);
+        assert_eq!(config.bump_for(&Entry::for_test("perf: a")), Bump::None);
+        assert_eq!(config.bump_for(&Entry::for_test("chore: a")), Bump::None);
+        assert_eq!(config.bump_for(&Entry::for_test("chore!: a\n\nBREAKING CHANGE: gone")), Bump::Major);
+    }
+
+    #[test]
+    fn test_apply_bump_keeps_zero_major() {
+        assert_eq!(apply_bump(&Version::new(1, 4, 2), Bump::Major), Version::new(2, 0, 0));
+        assert_eq!(apply_bump(&Version::new(1, 4, 2), Bump::Minor), Version::new(1, 5, 0));
+        assert_eq!(apply_bump(&Version::new(1, 4, 2), Bump::Patch), Version::new(1, 4, 3));
+        assert_eq!(apply_bump(&Version::new(0, 4, 2), Bump::Major), Version::new(0, 5, 0));
+        assert_eq!(apply_bump(&Version::new(0, 4, 2), Bump::None), Version::new(0, 4, 2));
+    }
+
+    #[test]
+    fn test_tag_pattern() {
+        let default = TagPattern::default();
+        assert_eq!(default.parse("v1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(default.parse("0.4.0"), Some(Version::new(0, 4, 0)));
+        assert_eq!(default.parse("deploy-prod"), None);
+        assert_eq!(default.format(&Version::new(1, 2, 3)), "v1.2.3");
+
+        let scoped = TagPattern::new("convy-cli@{version}").unwrap();
+        assert_eq!(scoped.parse("convy-cli@2.0.0-rc.1"), Some(Version::parse("2.0.0-rc.1").unwrap()));
+        assert_eq!(scoped.parse("v2.0.0"), None);
+        assert_eq!(scoped.parse("2.0.0"), None);
+        assert_eq!(scoped.format(&Version::new(2, 0, 0)), "convy-cli@2.0.0");
+        assert!(TagPattern::new("release").is_err());
+    }
+
+    #[test]
+    fn test_bump_version_prereleases() {
+        let v = |s: &str| Version::parse(s).unwrap();
+        let last_final = v("1.3.0");
+        assert_eq!(bump_version(Some(&last_final), Some(&last_final), Bump::Minor, Some("rc")).unwrap(), v("1.4.0-rc.1"));
+        assert_eq!(bump_version(Some(&v("1.4.0-rc.1")), Some(&last_final), Bump::Minor, Some("rc")).unwrap(), v("1.4.0-rc.2"));
+        assert_eq!(bump_version(Some(&v("1.4.0-beta.3")), Some(&last_final), Bump::Patch, Some("rc")).unwrap(), v("1.4.0-rc.1"));
+        assert_eq!(bump_version(Some(&v("1.4.0-rc.2")), Some(&last_final), Bump::Patch, None).unwrap(), v("1.4.0"));
+        assert_eq!(bump_version(Some(&v("1.4.0-rc.2")), Some(&last_final), Bump::Major, Some("rc")).unwrap(), v("2.0.0-rc.1"));
+        assert!(bump_version(None, None, Bump::Minor, Some("rc!")).is_err());
+    }
+}
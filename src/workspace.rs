@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::manifest::{member_dirs, parse_toml, read_optional, set_string};
+
+// Dependency tables of a manifest, at the top level and under `[target.'cfg(...)']`
+const DEPENDENCY_KINDS: [&str; 3] = ["dependencies", "build-dependencies", "dev-dependencies"];
+const WORKSPACE_DEPENDENCIES: &str = "workspace.dependencies";
+
+/// A crate of the Cargo workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub dir: PathBuf,
+    pub dependencies: Vec<Dependency>,
+}
+
+/// One entry of a member's dependency tables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,  // The crate depended on, following `package = "..."` renames
+    pub kind: String,  // "dependencies", "build-dependencies" or "dev-dependencies"
+    pub workspace: bool, // Inherited from `[workspace.dependencies]`
+}
+
+/// A version requirement on a workspace crate that no longer admits its new version.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RequirementChange {
+    pub manifest: PathBuf,
+    pub dependent: Option<String>, // `None` for `[workspace.dependencies]`
+    pub dependency: String,
+    pub kind: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Reads the crates of the workspace rooted at `root`: the root package, if any, and every `workspace.members` entry
+/// that `workspace.exclude` does not leave out.
+pub fn members(root: &Path) -> Result<Vec<Member>, String> {
+    let manifest = root.join("Cargo.toml");
+    let content = read_optional(&manifest)?.ok_or_else(|| format!("{} not found", manifest.display()))?;
+    let doc = parse_toml(&content).map_err(|e| format!("{}: {}", manifest.display(), e))?;
+
+    let mut dirs = vec![root.to_path_buf()];
+    if let Some(workspace) = doc.get("workspace") {
+        dirs.extend(member_dirs(root, workspace));
+    }
+
+    let mut members = Vec::new();
+    for dir in dirs {
+        let manifest = dir.join("Cargo.toml");
+        let Some(content) = read_optional(&manifest)? else {
+            continue;
+        };
+        let mut doc = parse_toml(&content).map_err(|e| format!("{}: {}", manifest.display(), e))?;
+        let Some(name) = doc.get("package").and_then(|p| p.get("name")).and_then(Item::as_str).map(str::to_string) else {
+            continue;
+        };
+        let mut dependencies = Vec::new();
+        visit_dependencies(&mut doc, &mut |kind, name, entry| {
+            if kind != WORKSPACE_DEPENDENCIES {
+                let workspace = entry.get("workspace").and_then(Item::as_bool).unwrap_or(false);
+                dependencies.push(Dependency { name: name.to_string(), kind: kind.to_string(), workspace });
+            }
+        });
+        members.push(Member { name, dir, dependencies });
+    }
+    Ok(members)
+}
+
+/// Finds (and with `write`, rewrites) the requirements on the crates in `versions` that their new version would not
+/// satisfy, across the root manifest and every member.
+pub fn requirement_changes(
+    root: &Path,
+    members: &[Member],
+    versions: &BTreeMap<String, Version>,
+    write: bool,
+) -> Result<Vec<RequirementChange>, String> {
+    let mut manifests: Vec<(PathBuf, Option<String>)> = vec![(root.join("Cargo.toml"), None)];
+    for member in members {
+        let manifest = member.dir.join("Cargo.toml");
+        match manifests.iter_mut().find(|(path, _)| same_dir(path, &manifest)) {
+            Some(existing) => existing.1 = Some(member.name.clone()),
+            None => manifests.push((manifest, Some(member.name.clone()))),
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (manifest, dependent) in manifests {
+        let Some(content) = read_optional(&manifest)? else {
+            continue;
+        };
+        let mut doc = parse_toml(&content).map_err(|e| format!("{}: {}", manifest.display(), e))?;
+        visit_dependencies(&mut doc, &mut |kind, name, entry| {
+            let Some(version) = versions.get(name) else {
+                return;
+            };
+            let Some(item) = requirement_item(entry) else {
+                return;
+            };
+            let from = item.as_str().unwrap_or_default().to_string();
+            if VersionReq::parse(&from).is_ok_and(|req| req.matches(version)) {
+                return;
+            }
+            let to = updated_requirement(&from, version);
+            set_string(item, &to);
+            changes.push(RequirementChange {
+                manifest: manifest.clone(),
+                dependent: dependent.clone().filter(|_| kind != WORKSPACE_DEPENDENCIES),
+                dependency: name.to_string(),
+                kind: kind.to_string(),
+                from,
+                to,
+            });
+        });
+
+        let updated = doc.to_string();
+        if write && updated != content {
+            fs::write(&manifest, updated).map_err(|e| format!("Failed to write {}: {}", manifest.display(), e))?;
+        }
+    }
+    Ok(changes)
+}
+
+/// The crates whose published manifest changes with `changes`: direct dependents, and members inheriting a rewritten
+/// `[workspace.dependencies]` entry. Dev-dependencies do not reach users of a crate, so they are left out.
+pub fn affected_crates(changes: &[RequirementChange], members: &[Member]) -> BTreeSet<String> {
+    let mut affected = BTreeSet::new();
+    for change in changes.iter().filter(|c| c.kind != "dev-dependencies") {
+        match &change.dependent {
+            Some(dependent) => {
+                affected.insert(dependent.clone());
+            }
+            None => affected.extend(
+                members
+                    .iter()
+                    .filter(|m| {
+                        m.dependencies.iter().any(|d| {
+                            d.workspace && d.name == change.dependency && d.kind != "dev-dependencies"
+                        })
+                    })
+                    .map(|m| m.name.clone()),
+            ),
+        }
+    }
+    affected
+}
+
+/// Member names ordered so that every crate comes after the workspace crates it depends on.
+pub fn publish_order(members: &[Member]) -> Vec<String> {
+    fn visit<'a>(member: &'a Member, members: &'a [Member], order: &mut Vec<String>, seen: &mut BTreeSet<&'a str>) {
+        if !seen.insert(&member.name) {
+            return;
+        }
+        for dependency in member.dependencies.iter().filter(|d| d.kind != "dev-dependencies") {
+            if let Some(local) = members.iter().find(|m| m.name == dependency.name) {
+                visit(local, members, order, seen);
+            }
+        }
+        order.push(member.name.clone());
+    }
+
+    let mut order = Vec::new();
+    let mut seen = BTreeSet::new();
+    for member in members {
+        visit(member, members, &mut order, &mut seen);
+    }
+    order
+}
+
+/// Whether two paths name the same location, ignoring `.` components.
+pub fn same_dir(a: &Path, b: &Path) -> bool {
+    let normal = |p: &Path| p.components().filter(|c| *c != Component::CurDir).collect::<PathBuf>();
+    normal(a) == normal(b)
+}
+
+// Calls `visit(kind, crate name, entry)` for every dependency in the manifest
+fn visit_dependencies(doc: &mut DocumentMut, visit: &mut dyn FnMut(&str, &str, &mut Item)) {
+    let root = doc.as_table_mut();
+    for kind in DEPENDENCY_KINDS {
+        if let Some(table) = root.get_mut(kind).and_then(Item::as_table_like_mut) {
+            visit_table(kind, table, visit);
+        }
+    }
+    if let Some(targets) = root.get_mut("target").and_then(Item::as_table_like_mut) {
+        for (_, target) in targets.iter_mut() {
+            for kind in DEPENDENCY_KINDS {
+                if let Some(table) = target.get_mut(kind).and_then(Item::as_table_like_mut) {
+                    visit_table(kind, table, visit);
+                }
+            }
+        }
+    }
+    let workspace = root.get_mut("workspace").and_then(|w| w.get_mut("dependencies"));
+    if let Some(table) = workspace.and_then(Item::as_table_like_mut) {
+        visit_table(WORKSPACE_DEPENDENCIES, table, visit);
+    }
+}
+
+fn visit_table(kind: &str, table: &mut dyn TableLike, visit: &mut dyn FnMut(&str, &str, &mut Item)) {
+    for (key, entry) in table.iter_mut() {
+        let name = entry.get("package").and_then(Item::as_str).unwrap_or(key.get()).to_string();
+        visit(kind, &name, entry);
+    }
+}
+
+// The version requirement of a dependency entry: `name = "1.2"` or `name = { version = "1.2", ... }`
+fn requirement_item(entry: &mut Item) -> Option<&mut Item> {
+    if entry.is_str() {
+        return Some(entry);
+    }
+    entry.get_mut("version").filter(|v| v.is_str())
+}
+
+/// A requirement admitting `version`, keeping the operator and precision of the old one (`"0.3"` becomes `"0.4"`,
+/// `"~1.2.0"` becomes `"~1.3.0"`). Pre-releases and compound requirements get the full version.
+pub fn updated_requirement(old: &str, version: &Version) -> String {
+    let old = old.trim();
+    let operator: String = old.chars().take_while(|c| matches!(c, '^' | '~' | '=' | '>' | '<')).collect();
+    let rest = old[operator.len()..].trim();
+    if operator.starts_with('<') || rest.contains([',', '*', '-']) || !version.pre.is_empty() {
+        return version.to_string();
+    }
+    let precise = match rest.split('.').count() {
+        1 => version.major.to_string(),
+        2 => format!("{}.{}", version.major, version.minor),
+        _ => version.to_string(),
+    };
+    format!("{}{}", operator, precise)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_updated_requirement() {
+        let version = Version::new(0, 4, 0);
+        assert_eq!(updated_requirement("0.3", &version), "0.4");
+        assert_eq!(updated_requirement("^0.3.1", &version), "^0.4.0");
+        assert_eq!(updated_requirement("=0.3.1", &version), "=0.4.0");
+        assert_eq!(updated_requirement(">=0.2, <0.4", &version), "0.4.0");
+        assert_eq!(updated_requirement("0.3", &Version::parse("0.4.0-rc.1").unwrap()), "0.4.0-rc.1");
+    }
+
+    #[test]
+    fn test_requirement_changes_and_order() {
+        let root = std::env::temp_dir().join(format!("convy-workspace-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (dir, manifest) in [
+            ("", "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"./crates/legacy\"]\n\n[workspace.dependencies]\ncore = { path = \"crates/core\", version = \"0.1\" }\n"),
+            ("crates/core", "[package]\nname = \"core\"\nversion = \"0.1.0\"\n"),
+            ("crates/cli", "[package]\nname = \"cli\"\nversion = \"1.0.0\"\n\n[dependencies]\nmacros = { path = \"../macros\", version = \"0.1.0\" } # local\n"),
+            ("crates/macros", "[package]\nname = \"macros\"\nversion = \"0.1.0\"\n\n[dependencies]\ncore.workspace = true\n"),
+            // Left out of the workspace, so it keeps its requirement and is not published
+            ("crates/legacy", "[package]\nname = \"legacy\"\nversion = \"0.1.0\"\n\n[dependencies]\ncore = \"0.1\"\n"),
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("Cargo.toml"), manifest).unwrap();
+        }
+
+        let members = members(&root).unwrap();
+        assert_eq!(publish_order(&members), ["core", "macros", "cli"]);
+
+        let versions = BTreeMap::from([("core".to_string(), Version::new(0, 2, 0))]);
+        let changes = requirement_changes(&root, &members, &versions, true).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].from.as_str(), changes[0].to.as_str()), ("0.1", "0.2"));
+        assert_eq!(affected_crates(&changes, &members), BTreeSet::from(["macros".to_string()]));
+        assert!(fs::read_to_string(root.join("crates/legacy/Cargo.toml")).unwrap().contains("core = \"0.1\""));
+
+        let versions = BTreeMap::from([("macros".to_string(), Version::new(0, 1, 5))]);
+        assert!(requirement_changes(&root, &members, &versions, false).unwrap().is_empty());
+        let versions = BTreeMap::from([("macros".to_string(), Version::new(0, 2, 0))]);
+        requirement_changes(&root, &members, &versions, true).unwrap();
+        let cli = fs::read_to_string(root.join("crates/cli/Cargo.toml")).unwrap();
+        assert!(cli.contains("macros = { path = \"../macros\", version = \"0.2.0\" } # local"), "{}", cli);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
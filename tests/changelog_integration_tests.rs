@@ -1,5 +1,6 @@
 use std::process::Command;
 use std::env;
+use std::fs;
 
 #[test]
 fn test_changelog_init_success_mocked() {
@@ -9,7 +10,13 @@ fn test_changelog_init_success_mocked() {
     // Path to the compiled binary (adjust if necessary)
     let binary_path = env!("CARGO_BIN_EXE_convy"); // Uses env var set by Cargo
 
+    // Run inside a scratch directory so the repository's own CHANGELOG.md is untouched
+    let work_dir = env::temp_dir().join(format!("convy-changelog-init-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&work_dir);
+    fs::create_dir_all(&work_dir).expect("Failed to create scratch directory");
+
     let output = Command::new(binary_path)
+        .current_dir(&work_dir)
         .arg("changelog")
         .arg("init")
         .output()
@@ -21,5 +28,633 @@ fn test_changelog_init_success_mocked() {
     assert!(output.status.success(), "Command did not execute successfully. Stderr: {}", String::from_utf8_lossy(&output.stderr));
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("Changelog initialized successfully."), "Stdout did not contain success message. Stdout: {}", stdout);
+    assert!(stdout.contains("Created CHANGELOG.md"), "Stdout did not contain success message. Stdout: {}", stdout);
+    assert!(work_dir.join("CHANGELOG.md").exists(), "CHANGELOG.md was not created");
+
+    let _ = fs::remove_dir_all(&work_dir);
+}
+
+// Creates an empty git repository in a scratch directory with a fixed identity
+fn scratch_repo(name: &str) -> std::path::PathBuf {
+    let dir = env::temp_dir().join(format!("convy-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Failed to create scratch directory");
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["config", "user.name", "Convy Test"]);
+    git(&dir, &["config", "user.email", "test@example.com"]);
+    git(&dir, &["config", "commit.gpgsign", "false"]);
+    dir
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("Failed to run git");
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn commit(dir: &std::path::Path, message: &str) {
+    git(dir, &["commit", "-q", "--allow-empty", "-m", message]);
+}
+
+fn convy(dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_convy"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+fn test_changelog_generate_uses_repository_config() {
+    let dir = scratch_repo("changelog-config");
+    fs::write(
+        dir.join(".convy.toml"),
+        r#"additional_types = ["ops"]
+
+[changelog]
+hidden_types = ["docs"]
+
+[[changelog.sections]]
+title = "Operations"
+types = ["ops"]
+
+[[changelog.sections]]
+title = "Features"
+types = ["feat"]
+"#,
+    )
+    .unwrap();
+
+    commit(&dir, "feat: add widget");
+    commit(&dir, "ops: rotate credentials");
+    commit(&dir, "docs: explain widget");
+
+    let output = convy(&dir, &["changelog", "generate"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let ops = stdout.find("### Operations\n- rotate credentials").expect("Operations section missing");
+    let feats = stdout.find("### Features\n- add widget").expect("Features section missing");
+    assert!(ops < feats, "Sections not in configured order: {}", stdout);
+    assert!(!stdout.contains("explain widget"), "Hidden type was rendered: {}", stdout);
+
+    // Commits matching no section are reported on stderr, leaving stdout a valid document
+    commit(&dir, "fix: stray");
+    let output = convy(&dir, &["changelog", "generate", "--format", "json"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("output is JSON");
+    assert_eq!(json["releases"][0]["sections"][0]["title"], "Operations");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 commit(s) in HEAD matched no changelog section (fix)"), "{}", stderr);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_changelog_links_follow_origin_remote() {
+    let dir = scratch_repo("changelog-links");
+    git(&dir, &["remote", "add", "origin", "git@github.com:acme/widget.git"]);
+    commit(&dir, "feat: first release");
+    git(&dir, &["tag", "v1.0.0"]);
+    commit(&dir, "fix: handle empty input");
+
+    assert!(convy(&dir, &["changelog", "init"]).status.success());
+    let output = convy(&dir, &["changelog", "generate", "--write"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let hash = git(&dir, &["rev-parse", "HEAD"]);
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(
+        content.contains(&format!("- handle empty input ([{}](https://github.com/acme/widget/commit/{}))", &hash[..7], hash.trim())),
+        "Entry link missing: {}",
+        content
+    );
+    assert!(content.ends_with("[Unreleased]: https://github.com/acme/widget/compare/v1.0.0...HEAD\n"), "{}", content);
+
+    assert!(convy(&dir, &["changelog", "release", "1.1.0"]).status.success());
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(content.ends_with(
+        "[Unreleased]: https://github.com/acme/widget/compare/v1.1.0...HEAD\n[1.1.0]: https://github.com/acme/widget/compare/v1.0.0...v1.1.0\n"
+    ), "{}", content);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_changelog_generate_full_writes_a_section_per_tag() {
+    let dir = scratch_repo("changelog-full");
+    commit(&dir, "feat: first");
+    git(&dir, &["tag", "v0.2.0"]);
+    commit(&dir, "fix: second");
+    git(&dir, &["tag", "v0.10.0"]);
+    commit(&dir, "feat: third");
+    git(&dir, &["tag", "not-a-version"]);
+    commit(&dir, "fix: pending");
+    fs::write(dir.join("CHANGELOG.md"), "stale content\n").unwrap();
+
+    let output = convy(&dir, &["changelog", "generate", "--full", "--write"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(!content.contains("stale content"));
+    let unreleased = content.find("## [Unreleased]\n").expect("Unreleased missing");
+    let newest = content.find("## [0.10.0] - ").expect("0.10.0 missing");
+    let oldest = content.find("## [0.2.0] - ").expect("0.2.0 missing");
+    assert!(unreleased < newest && newest < oldest, "Versions out of order: {}", content);
+    assert!(content[unreleased..newest].contains("- third") && content[unreleased..newest].contains("- pending"));
+    assert!(content[newest..oldest].contains("- second") && !content[newest..oldest].contains("- first"));
+    assert!(content[oldest..].contains("- first"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_version_next_follows_commits_since_last_tag() {
+    let dir = scratch_repo("version-next");
+    commit(&dir, "feat: initial");
+    git(&dir, &["tag", "v1.2.3"]);
+    commit(&dir, "fix: crash");
+    commit(&dir, "docs: readme");
+
+    let output = convy(&dir, &["version", "next"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1.2.4\n");
+
+    commit(&dir, "feat: widgets");
+    let output = convy(&dir, &["version", "next", "--explain"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1.3.0\n");
+    let explanation = String::from_utf8_lossy(&output.stderr);
+    assert!(explanation.contains("minor") && explanation.contains("feat: widgets"), "{}", explanation);
+    assert!(!explanation.contains("docs: readme"), "{}", explanation);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_changelog_prereleases_collapse_into_final_release() {
+    let dir = scratch_repo("changelog-prerelease");
+    commit(&dir, "feat: base");
+    git(&dir, &["tag", "v1.3.0"]);
+    assert!(convy(&dir, &["changelog", "init"]).status.success());
+
+    commit(&dir, "feat: widgets");
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    let output = convy(&dir, &["changelog", "release", "--prerelease", "rc"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    git(&dir, &["tag", "v1.4.0-rc.1"]);
+
+    commit(&dir, "fix: widget crash");
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    assert!(convy(&dir, &["changelog", "release", "--prerelease", "rc"]).status.success());
+    git(&dir, &["tag", "v1.4.0-rc.2"]);
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(content.contains("## [1.4.0-rc.2] - ") && content.contains("## [1.4.0-rc.1] - "), "{}", content);
+
+    let output = convy(&dir, &["changelog", "release", "1.3.5"]);
+    assert!(!output.status.success(), "Older version was accepted");
+    let output = convy(&dir, &["changelog", "release", "not-a-version"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid version 'not-a-version'"));
+
+    let output = convy(&dir, &["changelog", "release"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(!content.contains("rc."), "Pre-release sections left behind: {}", content);
+    let release = content.find("## [1.4.0] - ").expect("1.4.0 missing");
+    assert!(content[release..].contains("- widgets") && content[release..].contains("- widget crash"), "{}", content);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_release_commits_and_tags() {
+    let dir = scratch_repo("release");
+    commit(&dir, "feat: base");
+    git(&dir, &["tag", "v1.0.0"]);
+    commit(&dir, "feat: widgets");
+
+    let output = convy(&dir, &["release", "--dry-run"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would release 1.1.0"));
+    assert!(!dir.join("CHANGELOG.md").exists(), "Dry run wrote the changelog");
+
+    let output = convy(&dir, &["release", "--bump", "major"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(git(&dir, &["log", "-1", "--format=%s"]).trim(), "chore(release): v2.0.0");
+    assert_eq!(git(&dir, &["status", "--porcelain"]).trim(), "");
+    let tag_message = git(&dir, &["tag", "-l", "--format=%(contents)", "v2.0.0"]);
+    assert!(tag_message.starts_with("v2.0.0\n\n### Features\n- widgets"), "{}", tag_message);
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(content.contains("## [2.0.0] - "), "{}", content);
+
+    // Progress goes to stderr so the JSON plan can be parsed even when the release is applied
+    commit(&dir, "fix: gadgets");
+    let output = convy(&dir, &["release", "--format", "json"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("output is JSON");
+    assert_eq!(json["releases"][0]["tag"], "v2.0.1");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Committed \"chore(release): v2.0.1\""));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_failed_release_leaves_the_tree_as_it_was() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = scratch_repo("release-rollback");
+    fs::write(dir.join("VERSION"), "1.0.0\n").unwrap();
+    git(&dir, &["add", "VERSION"]);
+    commit(&dir, "feat: base");
+    let head = git(&dir, &["rev-parse", "HEAD"]);
+
+    // The release commit must pass the repository's own rules before anything is written
+    fs::write(dir.join(".convy.toml"), "types = [\"feat\", \"fix\"]\n").unwrap();
+    let output = convy(&dir, &["release"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("chore(release): v0.1.0"), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(!dir.join("CHANGELOG.md").exists());
+    fs::remove_file(dir.join(".convy.toml")).unwrap();
+
+    // A hook that rejects the commit once the files are written and staged
+    let hook = dir.join(".git/hooks/commit-msg");
+    fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    let output = convy(&dir, &["release"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("rolled back"), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(git(&dir, &["rev-parse", "HEAD"]), head);
+    assert_eq!(git(&dir, &["status", "--porcelain"]), "");
+    assert_eq!(git(&dir, &["tag", "--list"]), "");
+    assert_eq!(fs::read_to_string(dir.join("VERSION")).unwrap(), "1.0.0\n");
+    assert!(!dir.join("CHANGELOG.md").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_version_set_and_release_bump_manifests() {
+    let dir = scratch_repo("version-set");
+    fs::write(dir.join("Cargo.toml"), "[package]\nname = \"demo\"\nversion = \"1.0.0\" # keep me\n").unwrap();
+    fs::write(dir.join("Cargo.lock"), "version = 3\n\n[[package]]\nname = \"demo\"\nversion = \"1.0.0\"\n").unwrap();
+    fs::write(dir.join("VERSION"), "1.0.0\n").unwrap();
+    fs::write(dir.join("main.py"), "__version__ = \"1.0.0\"\n").unwrap();
+    fs::write(
+        dir.join(".convy.toml"),
+        "[[version.replace]]\nfile = \"main.py\"\npattern = '__version__ = \"([^\"]+)\"'\n",
+    )
+    .unwrap();
+    git(&dir, &["add", "."]);
+    commit(&dir, "feat: base");
+    git(&dir, &["tag", "v1.0.0"]);
+
+    let output = convy(&dir, &["version", "set", "1.0.1"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("Cargo.toml")).unwrap(), "[package]\nname = \"demo\"\nversion = \"1.0.1\" # keep me\n");
+    assert!(fs::read_to_string(dir.join("Cargo.lock")).unwrap().contains("version = \"1.0.1\""));
+    assert_eq!(fs::read_to_string(dir.join("main.py")).unwrap(), "__version__ = \"1.0.1\"\n");
+    git(&dir, &["checkout", "--", "."]);
+
+    commit(&dir, "fix: crash");
+    let output = convy(&dir, &["release"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("VERSION")).unwrap(), "1.0.1\n");
+    let files = git(&dir, &["show", "--name-only", "--format=", "HEAD"]);
+    for file in ["CHANGELOG.md", "Cargo.toml", "Cargo.lock", "VERSION", "main.py"] {
+        assert!(files.contains(file), "{} missing from release commit: {}", file, files);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_tag_pattern_ignores_other_tags() {
+    let dir = scratch_repo("tag-pattern");
+    fs::write(dir.join(".convy.toml"), "[version]\ntag_pattern = \"convy-cli@{version}\"\n").unwrap();
+    commit(&dir, "feat: base");
+    git(&dir, &["tag", "convy-cli@1.0.0"]);
+    commit(&dir, "feat: unrelated release");
+    git(&dir, &["tag", "v9.0.0"]);
+    commit(&dir, "fix: crash");
+    git(&dir, &["tag", "deploy-prod"]);
+
+    let output = convy(&dir, &["version", "next"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1.1.0\n");
+
+    let output = convy(&dir, &["changelog", "generate"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("range: convy-cli@1.0.0..HEAD"));
+
+    let output = convy(&dir, &["release", "--dry-run"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would create tag convy-cli@1.1.0"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_packages_release_independently() {
+    let dir = scratch_repo("packages");
+    fs::write(
+        dir.join(".convy.toml"),
+        "[[packages]]\nname = \"api\"\npath = \"api\"\n\n[[packages]]\nname = \"web\"\npath = \"web/**\"\nscopes = [\"ui\"]\n",
+    )
+    .unwrap();
+    for package in ["api", "web"] {
+        fs::create_dir_all(dir.join(package)).unwrap();
+        fs::write(dir.join(package).join("VERSION"), "0.1.0\n").unwrap();
+    }
+    git(&dir, &["add", "."]);
+    commit(&dir, "chore: initial layout");
+    git(&dir, &["tag", "api@0.1.0"]);
+    git(&dir, &["tag", "web@0.1.0"]);
+
+    fs::write(dir.join("api").join("server.rs"), "fn main() {}\n").unwrap();
+    git(&dir, &["add", "."]);
+    commit(&dir, "feat: add the server");
+    commit(&dir, "fix(ui): align the header");
+
+    let output = convy(&dir, &["changelog", "generate", "--package", "api"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("add the server") && !stdout.contains("align the header"), "Stdout: {}", stdout);
+
+    let output = convy(&dir, &["release"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(dir.join("api/VERSION")).unwrap(), "0.2.0\n");
+    assert_eq!(fs::read_to_string(dir.join("web/VERSION")).unwrap(), "0.1.1\n");
+    assert!(fs::read_to_string(dir.join("web/CHANGELOG.md")).unwrap().contains("align the header"));
+    assert_eq!(git(&dir, &["log", "-1", "--format=%s"]).trim(), "chore(release): api@0.2.0, web@0.1.1");
+    let tags = git(&dir, &["tag", "--points-at", "HEAD"]);
+    assert!(tags.contains("api@0.2.0") && tags.contains("web@0.1.1"), "Tags: {}", tags);
+
+    let output = convy(&dir, &["release", "--package", "web"]);
+    assert!(!output.status.success());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_workspace_release_propagates_requirements() {
+    let dir = scratch_repo("workspace");
+    fs::write(
+        dir.join(".convy.toml"),
+        "[[packages]]\nname = \"core\"\npath = \"crates/core\"\n\n[[packages]]\nname = \"cli\"\npath = \"crates/cli\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+    for (name, extra) in [("core", ""), ("cli", "\n[dependencies]\ncore = { path = \"../core\", version = \"0.1\" }\n")] {
+        fs::create_dir_all(dir.join("crates").join(name)).unwrap();
+        fs::write(
+            dir.join("crates").join(name).join("Cargo.toml"),
+            format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n{}", name, extra),
+        )
+        .unwrap();
+    }
+    git(&dir, &["add", "."]);
+    commit(&dir, "chore: initial layout");
+    git(&dir, &["tag", "core@0.1.0"]);
+    git(&dir, &["tag", "cli@0.1.0"]);
+    fs::write(dir.join("crates/core/lib.rs"), "pub fn run() {}\n").unwrap();
+    git(&dir, &["add", "."]);
+    commit(&dir, "feat: add run");
+
+    let output = convy(&dir, &["release", "--dry-run", "--format", "json"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).expect("plan is JSON");
+    let releases: Vec<(&str, &str)> = plan["releases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["crate"].as_str().unwrap(), r["version"].as_str().unwrap()))
+        .collect();
+    assert_eq!(releases, [("core", "0.2.0"), ("cli", "0.1.1")]);
+    assert_eq!(plan["requirements"][0]["to"], "0.2");
+
+    let output = convy(&dir, &["release"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let cli = fs::read_to_string(dir.join("crates/cli/Cargo.toml")).unwrap();
+    assert!(cli.contains("version = \"0.1.1\"") && cli.contains("core = { path = \"../core\", version = \"0.2\" }"), "{}", cli);
+    assert_eq!(git(&dir, &["log", "-1", "--format=%s"]).trim(), "chore(release): core@0.2.0, cli@0.1.1");
+    assert_eq!(git(&dir, &["status", "--porcelain"]), "");
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_generate_write_keeps_hand_written_notes() {
+    let dir = scratch_repo("hand-notes");
+    commit(&dir, "feat: first");
+    // A changelog maintained by hand, without the generated-block markers
+    let path = dir.join("CHANGELOG.md");
+    fs::write(&path, "# Changelog\n\n## [Unreleased]\n\nHighlight: a hand-written note.\n").unwrap();
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    let hash = git(&dir, &["rev-parse", "--short=7", "HEAD"]);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        format!(
+            "# Changelog\n\n## [Unreleased]\n\nHighlight: a hand-written note.\n\n<!-- convy:start -->\n### Features\n- first ({})\n<!-- convy:end -->\n\n",
+            hash.trim()
+        )
+    );
+
+    commit(&dir, "fix: second");
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    let once = fs::read_to_string(&path).unwrap();
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), once);
+    assert!(once.contains("Highlight: a hand-written note.") && once.contains("first") && once.contains("second"), "{}", once);
+
+    assert!(convy(&dir, &["changelog", "release", "1.0.0"]).status.success());
+    let released = fs::read_to_string(&path).unwrap();
+    assert!(released.contains("## [1.0.0]") && released.contains("Highlight") && !released.contains("convy:start"), "{}", released);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_generate_write_adopts_a_changelog_without_markers() {
+    let dir = scratch_repo("no-markers");
+    commit(&dir, "feat: first");
+    let first = git(&dir, &["rev-parse", "--short=7", "HEAD"]).trim().to_string();
+    commit(&dir, "fix: second");
+    let second = git(&dir, &["rev-parse", "--short=7", "HEAD"]).trim().to_string();
+    // As written by `generate --write` before it added markers, plus an entry written by hand
+    let path = dir.join("CHANGELOG.md");
+    fs::write(
+        &path,
+        format!("# Changelog\n\n## [Unreleased]\n\n### Features\n- first ({})\n\n### Bug Fixes\n- second ({})\n- docs typo\n", first, second),
+    )
+    .unwrap();
+
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    let once = fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        once,
+        format!(
+            "# Changelog\n\n## [Unreleased]\n\n### Bug Fixes\n- docs typo\n\n<!-- convy:start -->\n### Features\n- first ({})\n\n### Bug Fixes\n- second ({})\n<!-- convy:end -->\n\n",
+            first, second
+        )
+    );
+    assert!(convy(&dir, &["changelog", "generate", "--write"]).status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), once);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_changelog_lint_after_release() {
+    let dir = scratch_repo("lint");
+    commit(&dir, "feat: first");
+    let output = convy(&dir, &["release", "--prerelease", "rc"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    commit(&dir, "fix: second");
+    let output = convy(&dir, &["release"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(git(&dir, &["tag", "--list"]), "v0.1.0\nv0.1.0-rc.1\n");
+    let content = fs::read_to_string(dir.join("CHANGELOG.md")).unwrap();
+    assert!(!content.contains("**release**"), "Release commit listed: {}", content);
+    let output = convy(&dir, &["changelog", "lint"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let path = dir.join("CHANGELOG.md");
+    let content = fs::read_to_string(&path).unwrap();
+    fs::write(&path, format!("{}\n## [0.0.9] - yesterday\n", content.trim_end())).unwrap();
+    let output = convy(&dir, &["changelog", "lint", "--no-tags"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("CHANGELOG.md:") && stderr.contains("Date 'yesterday' of 0.0.9"), "{}", stderr);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_generate_formats() {
+    let dir = scratch_repo("formats");
+    commit(&dir, "feat: first");
+    git(&dir, &["tag", "v1.0.0"]);
+    commit(&dir, "fix: second");
+
+    let output = convy(&dir, &["changelog", "generate", "--full", "--format", "json"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("output is JSON");
+    let releases = json["releases"].as_array().unwrap();
+    assert_eq!(releases.len(), 2);
+    assert_eq!(releases[0]["version"], "Unreleased");
+    assert_eq!(releases[0]["sections"][0]["commits"][0]["subject"], "second");
+    assert_eq!(releases[1]["version"], "1.0.0");
+    assert_eq!(releases[1]["tag"], "v1.0.0");
+
+    let output = convy(&dir, &["changelog", "generate", "--format", "rst"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Unreleased\n----------\n\nBug Fixes\n~~~~~~~~~\n\n* second"), "{}", stdout);
+
+    let output = convy(&dir, &["changelog", "generate", "--write", "--format", "html"]);
+    assert!(!output.status.success());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_generate_distribution_changelogs() {
+    let dir = scratch_repo("packaging");
+    fs::write(dir.join(".convy.toml"), "[changelog.packaging]\nname = \"widget\"\ndistribution = \"bookworm\"\n").unwrap();
+    commit(&dir, "feat: first");
+    git(&dir, &["tag", "v1.0.0"]);
+    commit(&dir, "fix: second");
+
+    let output = convy(&dir, &["changelog", "generate", "--full", "--format", "debian"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("widget (1.0.1-1) UNRELEASED; urgency=medium\n\n  * second\n\n -- Convy Test <test@example.com>  "), "{}", stdout);
+    assert!(stdout.contains("widget (1.0.0-1) bookworm; urgency=medium\n\n  * first\n"), "{}", stdout);
+
+    let output = convy(&dir, &["changelog", "generate", "--format", "rpm"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(" Convy Test <test@example.com> - 1.0.1-1\n- second\n"), "{}", stdout);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_generate_contributors() {
+    let dir = scratch_repo("contributors");
+    fs::write(dir.join(".convy.toml"), "[changelog.contributors]\nnew_contributors = true\n").unwrap();
+    fs::write(
+        dir.join(".mailmap"),
+        "Convy Test <test@example.com> <old@example.com>\nAnn Lee <ann@example.com> <ann@old.example.com>\n",
+    )
+    .unwrap();
+    commit(&dir, "feat: first");
+    git(&dir, &["tag", "v1.0.0"]);
+    git(&dir, &["-c", "user.name=Old Name", "-c", "user.email=old@example.com", "commit", "-q", "--allow-empty", "-m", "fix: second"]);
+    commit(&dir, "feat: third\n\nCo-authored-by: Ann <ann@old.example.com>");
+    git(&dir, &[
+        "-c", "user.name=dependabot[bot]",
+        "-c", "user.email=49699333+dependabot[bot]@users.noreply.github.com",
+        "commit", "-q", "--allow-empty", "-m", "chore: bump serde",
+    ]);
+
+    let output = convy(&dir, &["changelog", "generate"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("### Contributors\n- Ann Lee\n- Convy Test\n\n### New Contributors\n- Ann Lee\n"),
+        "{}",
+        stdout
+    );
+
+    let output = convy(&dir, &["changelog", "generate", "--format", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).expect("output is JSON");
+    let third = &json["releases"][0]["sections"][0]["commits"][0];
+    assert_eq!(third["subject"], "third");
+    assert_eq!(third["authors"][1]["name"], "Ann Lee");
+    assert_eq!(third["authors"][1]["email"], "ann@example.com");
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_generate_cancels_reverted_commits() {
+    let dir = scratch_repo("reverts");
+    for (file, message) in [("a.txt", "feat: add a"), ("b.txt", "feat: add b")] {
+        fs::write(dir.join(file), message).unwrap();
+        git(&dir, &["add", file]);
+        git(&dir, &["commit", "-q", "-m", message]);
+        if file == "a.txt" {
+            git(&dir, &["tag", "v1.0.0"]);
+        }
+    }
+    fs::write(dir.join("c.txt"), "c").unwrap();
+    git(&dir, &["add", "c.txt"]);
+    git(&dir, &["commit", "-q", "-m", "fix: add c"]);
+    git(&dir, &["revert", "--no-edit", "HEAD~1"]);
+    commit(&dir, "revert: fix: add c");
+    git(&dir, &["revert", "--no-edit", "v1.0.0"]);
+
+    let output = convy(&dir, &["changelog", "generate"]);
+    assert!(output.status.success(), "Stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("### Reverts\n- feat: add a ("), "{}", stdout);
+    assert!(!stdout.contains("add b") && !stdout.contains("add c"), "{}", stdout);
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_malformed_config_fails_every_command() {
+    let dir = scratch_repo("bad-config");
+    fs::write(dir.join("MSG"), "fix: something\n").unwrap();
+
+    assert!(convy(&dir, &["fix", "MSG", "--check"]).status.success());
+    fs::write(dir.join(".convy.toml"), "types = [\"fix\"\n").unwrap();
+    for args in [&["fix", "MSG", "--check"][..], &["parse", "fix: something"], &["version", "next"]] {
+        let output = convy(&dir, args);
+        assert!(!output.status.success(), "{:?} succeeded", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to parse .convy.toml"), "{:?}", args);
+    }
+
+    let _ = fs::remove_dir_all(&dir);
 }